@@ -0,0 +1,137 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Combines OWL cardinality constraints,
+//! and checks them for consistency.
+
+use std::fmt;
+
+/**
+ * How many values a property may have,
+ * as far as that is constrained at all.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Cardinality {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+/**
+ * A set of cardinality constraints that do not agree with each other.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardinalityConflict {
+    pub exact: Vec<u64>,
+    pub min: Vec<u64>,
+    pub max: Vec<u64>,
+}
+
+impl fmt::Display for CardinalityConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        for (name, values) in [
+            ("owl:cardinality", &self.exact),
+            ("owl:minCardinality", &self.min),
+            ("owl:maxCardinality", &self.max),
+        ] {
+            if !values.is_empty() {
+                let values = values
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                parts.push(format!("{name} {values}"));
+            }
+        }
+        write!(f, "conflicting cardinalities: {}", parts.join("; "))
+    }
+}
+
+impl Cardinality {
+    /**
+     * Combines exact, minimum and maximum cardinalities
+     * the way OWL does, meaning that all of them have to hold.
+     *
+     * Redundant constraints (e.g. `owl:cardinality 1`
+     * together with `owl:maxCardinality 3`) are no conflict.
+     *
+     * # Errors
+     *
+     * If the constraints disagree with each other,
+     * meaning that two exact cardinalities differ,
+     * or the combined cardinality is unsatisfiable (`min > max`),
+     * in which case the error still carries the combined cardinality.
+     */
+    pub fn combine(
+        exact: &[u64],
+        min: &[u64],
+        max: &[u64],
+    ) -> Result<Self, (Self, CardinalityConflict)> {
+        let combined = Self {
+            min: exact.iter().chain(min).max().copied(),
+            max: exact.iter().chain(max).min().copied(),
+        };
+        let exact_conflicts = exact
+            .first()
+            .is_some_and(|first| exact.iter().any(|val| val != first));
+        if exact_conflicts || !combined.is_satisfiable() {
+            Err((
+                combined,
+                CardinalityConflict {
+                    exact: exact.to_vec(),
+                    min: min.to_vec(),
+                    max: max.to_vec(),
+                },
+            ))
+        } else {
+            Ok(combined)
+        }
+    }
+
+    /**
+     * Whether there can be any number of values
+     * that satisfies this cardinality.
+     */
+    #[must_use]
+    pub const fn is_satisfiable(self) -> bool {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => min <= max,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redundant_but_consistent() {
+        let exactly_one = Cardinality {
+            min: Some(1),
+            max: Some(1),
+        };
+        assert_eq!(Cardinality::combine(&[1], &[], &[3]), Ok(exactly_one));
+        assert_eq!(
+            Cardinality::combine(&[1], &[0, 1], &[1, 2]),
+            Ok(exactly_one)
+        );
+        assert_eq!(
+            Cardinality::combine(&[], &[1, 2], &[5, 3]),
+            Ok(Cardinality {
+                min: Some(2),
+                max: Some(3),
+            })
+        );
+    }
+
+    #[test]
+    fn conflicting() {
+        assert!(Cardinality::combine(&[1, 2], &[], &[]).is_err());
+        assert!(Cardinality::combine(&[1], &[2], &[]).is_err());
+        assert!(Cardinality::combine(&[2], &[], &[1]).is_err());
+        let (combined, _) = Cardinality::combine(&[], &[3], &[1]).unwrap_err();
+        assert!(!combined.is_satisfiable());
+    }
+}
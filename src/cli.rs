@@ -0,0 +1,270 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::{Parser, ValueEnum};
+use enum_map::EnumMap;
+use oxigraph::model::NamedNode;
+
+use owl2shacl::config::{
+    ClassHierarchy, Config, DisjointClasses, Inheritance, InputFormat, OdityHandling, OutputFormat,
+    PropertyShapes, RDProperty,
+};
+use owl2shacl::naming::ShapeNaming;
+
+/// A setting, either for one specific of `rdfs:range`/`rdfs:domain`,
+/// or - if `None` - for both of them.
+type RDSetting<V> = (Option<RDProperty>, V);
+/// An oddity handling for `rdfs:range` and/or `rdfs:domain`.
+type RDOdityHandling = RDSetting<OdityHandling>;
+
+/**
+ * Parses a CLI value of the form `[range|domain=]VALUE`,
+ * e.g. `[range|domain=]ignore|warn|error`.
+ */
+fn parse_rd_setting<V: ValueEnum>(value: &str) -> Result<RDSetting<V>, String> {
+    let (prop, handling) = match value.split_once('=') {
+        Some((prop, handling)) => {
+            let prop = RDProperty::from_str(prop).map_err(|_err| {
+                format!("Invalid property '{prop}'; valid values are: range, domain")
+            })?;
+            (Some(prop), handling)
+        }
+        None => (None, value),
+    };
+    let handling = V::from_str(handling, true)?;
+    Ok((prop, handling))
+}
+
+fn parse_iri(value: &str) -> Result<NamedNode, String> {
+    NamedNode::new(value).map_err(|err| format!("Invalid IRI '{value}': {err}"))
+}
+
+fn apply<V: Copy>(map: &mut EnumMap<RDProperty, V>, settings: &[RDSetting<V>]) {
+    for (prop, setting) in settings {
+        match prop {
+            Some(prop) => map[*prop] = *setting,
+            None => map.values_mut().for_each(|val| *val = *setting),
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Cli {
+    /// The OWL ontology file(s) to convert
+    /// (Turtle, RDF/XML, N-Triples, N-Quads or `TriG`);
+    /// multiple files get merged into one graph before conversion.
+    #[arg(required = true, value_name = "ONTOLOGY")]
+    pub inputs: Vec<PathBuf>,
+
+    /// The file to write the SHACL shapes to,
+    /// in the format given by `--output-format`,
+    /// or else the one inferred from its extension (Turtle by default);
+    /// `-` or not specifying it, means stdout.
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Base IRI to resolve relative IRIs in the input ontologies against.
+    #[arg(short, long, value_name = "IRI")]
+    pub base: Option<String>,
+
+    /// The RDF serialization of the input ontologies;
+    /// by default inferred from the extension of each file,
+    /// or - failing that - from its content.
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub input_format: Option<InputFormat>,
+
+    /// A named graph to load from N-Quads and `TriG` input ontologies,
+    /// in addition to their default graph;
+    /// if not given, all named graphs are loaded.
+    /// May be given multiple times.
+    #[arg(long, value_name = "IRI", value_parser = parse_iri)]
+    pub input_graph: Vec<NamedNode>,
+
+    /// What to do if a property's `rdfs:range`/`rdfs:domain`
+    /// specifies a list of classes that all have to apply.
+    /// May be given multiple times.
+    #[arg(long, value_name = "[PROP=]ACTION", value_parser = parse_rd_setting::<OdityHandling>)]
+    pub and_list_detected: Vec<RDOdityHandling>,
+
+    /// What to do if a single property uses both `rdfs:range` and `*:rangeIncludes`
+    /// (or respectively for domain).
+    /// May be given multiple times.
+    #[arg(long, value_name = "[PROP=]ACTION", value_parser = parse_rd_setting::<OdityHandling>)]
+    pub style_mix_property: Vec<RDOdityHandling>,
+
+    /// What to do if some properties use `rdfs:range`
+    /// and others `*:rangeIncludes` (or respectively for domain).
+    /// May be given multiple times.
+    #[arg(long, value_name = "[PROP=]ACTION", value_parser = parse_rd_setting::<OdityHandling>)]
+    pub style_mix_ontology: Vec<RDOdityHandling>,
+
+    /// What to do if a construct is found that can not be converted into SHACL;
+    /// unless this is `error`, the offending axiom is skipped.
+    #[arg(long, value_enum, value_name = "ACTION")]
+    pub unsupported_construct: Option<OdityHandling>,
+
+    /// What to do if the cardinalities given for a property disagree;
+    /// unless this is `error`, all of them are combined.
+    #[arg(long, value_enum, value_name = "ACTION")]
+    pub cardinality_conflict: Option<OdityHandling>,
+
+    /// Additionally link property shapes to the node shape
+    /// of their range class with `sh:node`.
+    #[arg(long)]
+    pub range_node_link: bool,
+
+    /// Mark the property shapes generated from qualified restrictions
+    /// with `sh:qualifiedValueShapesDisjoint true`.
+    #[arg(long)]
+    pub qualified_value_shapes_disjoint: bool,
+
+    /// Do not translate `owl:FunctionalProperty` into `sh:maxCount 1`.
+    #[arg(long)]
+    pub no_functional: bool,
+
+    /// Do not generate inverse `sh:maxCount 1` shapes
+    /// for `owl:InverseFunctionalProperty`.
+    #[arg(long)]
+    pub no_inverse_functional: bool,
+
+    /// How sub-properties inherit the range/domain of their super-properties.
+    /// May be given multiple times.
+    #[arg(long, value_name = "[PROP=]POLICY", value_parser = parse_rd_setting::<Inheritance>)]
+    pub sub_property_inheritance: Vec<RDSetting<Inheritance>>,
+
+    /// Let the property shapes of super-properties also match
+    /// the values of their sub-properties, using `sh:alternativePath`.
+    #[arg(long)]
+    pub sub_property_alternative_path: bool,
+
+    /// How the properties of an `owl:inverseOf` pair get their range/domain
+    /// from the domain/range of each other.
+    /// May be given multiple times.
+    #[arg(long, value_name = "[PROP=]POLICY", value_parser = parse_rd_setting::<Inheritance>)]
+    pub inverse_inheritance: Vec<RDSetting<Inheritance>>,
+
+    /// Add property shapes with `sh:path [ sh:inversePath <property> ]`
+    /// to the node shapes of the range classes of inverse properties.
+    #[arg(long)]
+    pub inverse_path: bool,
+
+    /// Translate symmetric, asymmetric, irreflexive and transitive properties
+    /// into SPARQL-based constraints (`sh:sparql`).
+    /// NOTE: These require a validator that supports SHACL-SPARQL.
+    #[arg(long)]
+    pub sparql_constraints: bool,
+
+    /// Whether the property shapes are shared by all the domains of a property,
+    /// or minted per class and property.
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub property_shapes: Option<PropertyShapes>,
+
+    /// How the node shapes of sub-classes get the constraints
+    /// of their super-classes' shapes.
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub class_hierarchy: Option<ClassHierarchy>,
+
+    /// How disjoint classes (`owl:disjointWith`, `owl:AllDisjointClasses`)
+    /// get converted.
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub disjoint_classes: Option<DisjointClasses>,
+
+    /// Generate closed node shapes,
+    /// which do not allow any properties without a property shape.
+    #[arg(long)]
+    pub closed: bool,
+
+    /// A property that is always allowed in closed node shapes,
+    /// in addition to `rdf:type`, e.g. `http://www.w3.org/2000/01/rdf-schema#label`.
+    /// May be given multiple times.
+    #[arg(long, value_name = "IRI", value_parser = parse_iri)]
+    pub ignored_property: Vec<NamedNode>,
+
+    /// How to name the generated shapes; one of:
+    /// `suffix[:SUFFIX]` (default: `suffix:Shape`, e.g. `ex:PersonShape`),
+    /// `prefix:PREFIX` (e.g. `prefix:Shape_` for `ex:Shape_Person`),
+    /// `namespace:FROM=TO` (e.g. `namespace:https://w3id.org/valueflows#=https://w3id.org/valueflows/shapes#`),
+    /// `hash[:NAMESPACE]` or `blank-node`.
+    #[arg(long, value_name = "STRATEGY")]
+    pub shape_naming: Option<ShapeNaming>,
+
+    /// What to do if the IRI of a generated shape
+    /// is already used in the source ontology,
+    /// or minted for another generated shape as well.
+    #[arg(long, value_enum, value_name = "ACTION")]
+    pub shape_name_collision: Option<OdityHandling>,
+
+    /// The RDF serialization to write the shapes in;
+    /// by default inferred from the extension of the output file,
+    /// falling back to Turtle.
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub output_format: Option<OutputFormat>,
+
+    /// The named graph to put the shapes into,
+    /// for the output formats supporting named graphs
+    /// (N-Quads, `TriG` and JSON-LD).
+    #[arg(long, value_name = "IRI", value_parser = parse_iri)]
+    pub shapes_graph: Option<NamedNode>,
+}
+
+impl Cli {
+    pub fn to_config(&self) -> Config {
+        let mut config = Config::default();
+        apply(&mut config.and_list_detected, &self.and_list_detected);
+        apply(&mut config.style_mix_property, &self.style_mix_property);
+        apply(&mut config.style_mix_ontology, &self.style_mix_ontology);
+        if let Some(handling) = self.unsupported_construct {
+            config.unsupported_construct = handling;
+        }
+        if let Some(handling) = self.cardinality_conflict {
+            config.cardinality_conflict = handling;
+        }
+        config.range_node_link = self.range_node_link;
+        config.qualified_value_shapes_disjoint = self.qualified_value_shapes_disjoint;
+        config.functional_property = !self.no_functional;
+        config.inverse_functional_property = !self.no_inverse_functional;
+        apply(
+            &mut config.sub_property_inheritance,
+            &self.sub_property_inheritance,
+        );
+        config.sub_property_alternative_path = self.sub_property_alternative_path;
+        apply(&mut config.inverse_inheritance, &self.inverse_inheritance);
+        config.inverse_path = self.inverse_path;
+        config.sparql_constraints = self.sparql_constraints;
+        if let Some(property_shapes) = self.property_shapes {
+            config.property_shapes = property_shapes;
+        }
+        if let Some(class_hierarchy) = self.class_hierarchy {
+            config.class_hierarchy = class_hierarchy;
+        }
+        if let Some(disjoint_classes) = self.disjoint_classes {
+            config.disjoint_classes = disjoint_classes;
+        }
+        config.closed = self.closed;
+        config.ignored_properties.clone_from(&self.ignored_property);
+        if let Some(shape_naming) = &self.shape_naming {
+            config.shape_naming = shape_naming.clone();
+        }
+        if let Some(handling) = self.shape_name_collision {
+            config.shape_name_collision = handling;
+        }
+        config.input_format = self.input_format;
+        config.input_graphs.clone_from(&self.input_graph);
+        config.output_format = self.output_format;
+        config.shapes_graph.clone_from(&self.shapes_graph);
+        config
+    }
+
+    /// The file to write the output to, or `None` for stdout.
+    pub fn output_file(&self) -> Option<&Path> {
+        self.output
+            .as_deref()
+            .filter(|path| path.as_os_str() != "-")
+    }
+}
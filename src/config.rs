@@ -2,9 +2,14 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::path::Path;
+
 use clap::ValueEnum;
 use enum_map::{Enum, EnumMap};
-use strum_macros::{EnumIter, EnumString, VariantNames, IntoStaticStr};
+use oxigraph::model::NamedNode;
+
+use crate::naming::ShapeNaming;
+use strum_macros::{EnumIter, EnumString, IntoStaticStr, VariantNames};
 
 /**
  * How to behave in case an odity is detected
@@ -22,32 +27,31 @@ use strum_macros::{EnumIter, EnumString, VariantNames, IntoStaticStr};
     PartialOrd,
     Copy,
     Clone,
+    Default,
 )]
 pub enum OdityHandling {
     Ignore,
+    #[default]
     Warn,
     Error,
 }
 
-impl Default for OdityHandling {
-    fn default() -> Self {
-        Self::Warn
-    }
-}
-
 impl OdityHandling {
+    #[must_use]
     pub const fn ignore(self) -> bool {
         matches!(self, Self::Ignore)
     }
 }
 
-#[derive(Clone, Copy, Debug, Enum, EnumIter)]
+#[derive(Clone, Copy, Debug, Enum, EnumIter, EnumString, VariantNames, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
 pub enum RDProperty {
     Range,
     Domain,
 }
 
 impl RDProperty {
+    #[must_use]
     pub const fn to_str(self) -> &'static str {
         match self {
             Self::Range => "range",
@@ -56,7 +60,237 @@ impl RDProperty {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+/**
+ * Whether and how a sub-property inherits
+ * the `rdfs:domain`/`rdfs:range` of its super-properties.
+ */
+#[derive(
+    Debug,
+    ValueEnum,
+    EnumString,
+    VariantNames,
+    EnumIter,
+    IntoStaticStr,
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    Default,
+)]
+pub enum Inheritance {
+    /// Never inherit
+    None,
+    /// Inherit only if the sub-property has none of its own
+    #[default]
+    IfMissing,
+    /// Always inherit, in addition to the sub-property's own ones
+    Always,
+}
+
+/**
+ * How the node shape of a sub-class
+ * gets the constraints of its super-classes.
+ */
+#[derive(
+    Debug,
+    ValueEnum,
+    EnumString,
+    VariantNames,
+    EnumIter,
+    IntoStaticStr,
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    Default,
+)]
+pub enum ClassHierarchy {
+    /// Do not relate the shapes of sub- and super-classes
+    None,
+    /// Reference the node shapes of the direct super-classes with `sh:node`
+    #[default]
+    Node,
+    /// Copy the property shapes of all (transitive) super-classes
+    /// into the node shape of the sub-class
+    Flatten,
+}
+
+/**
+ * How disjoint classes (`owl:disjointWith`, `owl:AllDisjointClasses`)
+ * get converted.
+ */
+#[derive(
+    Debug,
+    ValueEnum,
+    EnumString,
+    VariantNames,
+    EnumIter,
+    IntoStaticStr,
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    Default,
+)]
+pub enum DisjointClasses {
+    /// Do not convert disjoint classes
+    None,
+    /// Add `sh:not [ sh:class <other> ]` to the node shape of each class,
+    /// for each class it is disjoint with
+    #[default]
+    Class,
+    /// Generate a single node shape per group of disjoint classes,
+    /// targeting all of them, and requiring each instance
+    /// to be of exactly one of them (`sh:xone`)
+    Group,
+}
+
+/**
+ * Whether the property shapes of properties
+ * are shared by the node shapes of all their domains,
+ * or each of them gets a copy of its own.
+ */
+#[derive(
+    Debug,
+    ValueEnum,
+    EnumString,
+    VariantNames,
+    EnumIter,
+    IntoStaticStr,
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    Default,
+)]
+pub enum PropertyShapes {
+    /// A single property shape per property (e.g. `ex:nameShape`),
+    /// shared by the node shapes of all its domains
+    #[default]
+    Global,
+    /// A named property shape per class and property,
+    /// e.g. `ex:PersonShape-name`
+    Named,
+    /// A blank property shape per class and property
+    Blank,
+}
+
+/**
+ * The RDF serialization of an input ontology.
+ */
+#[derive(
+    Debug,
+    ValueEnum,
+    EnumString,
+    VariantNames,
+    EnumIter,
+    IntoStaticStr,
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    Default,
+)]
+pub enum InputFormat {
+    /// Turtle (`.ttl`)
+    #[default]
+    Turtle,
+    /// N-Triples (`.nt`)
+    NTriples,
+    /// N-Quads (`.nq`)
+    NQuads,
+    /// `TriG` (`.trig`)
+    #[value(name = "trig")]
+    TriG,
+    /// RDF/XML (`.rdf`, `.xml`)
+    RdfXml,
+}
+
+impl InputFormat {
+    /**
+     * The format commonly used for files with the extension of `path`.
+     * `.owl` files are left to content sniffing,
+     * as they come in RDF/XML, Turtle and OWL/XML alike.
+     */
+    #[must_use]
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ttl" | "turtle" => Some(Self::Turtle),
+            "nt" => Some(Self::NTriples),
+            "nq" => Some(Self::NQuads),
+            "trig" => Some(Self::TriG),
+            "rdf" | "xml" => Some(Self::RdfXml),
+            _ => None,
+        }
+    }
+
+    /**
+     * Whether this format supports named graphs.
+     */
+    #[must_use]
+    pub const fn has_graphs(self) -> bool {
+        match self {
+            Self::NQuads | Self::TriG => true,
+            Self::Turtle | Self::NTriples | Self::RdfXml => false,
+        }
+    }
+}
+
+/**
+ * The RDF serialization to write the shapes in.
+ */
+#[derive(
+    Debug,
+    ValueEnum,
+    EnumString,
+    VariantNames,
+    EnumIter,
+    IntoStaticStr,
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    Default,
+)]
+pub enum OutputFormat {
+    /// Turtle (`.ttl`)
+    #[default]
+    Turtle,
+    /// N-Triples (`.nt`)
+    NTriples,
+    /// N-Quads (`.nq`), with the shapes in the shapes graph
+    NQuads,
+    /// `TriG` (`.trig`), with the shapes in the shapes graph
+    #[value(name = "trig")]
+    TriG,
+    /// RDF/XML (`.rdf`, `.owl`, `.xml`)
+    RdfXml,
+    /// JSON-LD (`.jsonld`, `.json`), with a compact context
+    JsonLd,
+}
+
+impl OutputFormat {
+    /**
+     * The format commonly used for files with the extension of `path`.
+     */
+    #[must_use]
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ttl" => Some(Self::Turtle),
+            "nt" => Some(Self::NTriples),
+            "nq" => Some(Self::NQuads),
+            "trig" => Some(Self::TriG),
+            "rdf" | "owl" | "xml" => Some(Self::RdfXml),
+            "jsonld" | "json" => Some(Self::JsonLd),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config /*<S: ::std::hash::BuildHasher>*/ {
     /**
      * What to do if the source Ontology contains properties
@@ -80,4 +314,197 @@ pub struct Config /*<S: ::std::hash::BuildHasher>*/ {
      * which is technically ok, but might be confusing.
      */
     pub style_mix_ontology: EnumMap<RDProperty, OdityHandling>,
+    /**
+     * What to do if the source Ontology contains a construct
+     * that can not be converted into SHACL,
+     * for example a property that is a blank node.
+     * If not handled as an error, the offending axiom is skipped,
+     * and the conversion continues with the rest of the ontology.
+     */
+    pub unsupported_construct: OdityHandling,
+    /**
+     * What to do if the cardinalities given for a property disagree,
+     * for example `owl:cardinality 1` together with `owl:minCardinality 2`,
+     * or `owl:minCardinality` being greater than `owl:maxCardinality`.
+     * Merely redundant ones, like `owl:cardinality 1`
+     * together with `owl:maxCardinality 3`, are no conflict.
+     * Unless handled as an error, all of them are combined
+     * (the greatest minimum and the smallest maximum win),
+     * the same way as OWL interprets them.
+     */
+    pub cardinality_conflict: OdityHandling,
+    /**
+     * Whether to additionally link a property shape
+     * to the node shape generated for its range class with `sh:node`,
+     * so values of the property also get validated against that shape.
+     * `sh:class` alone only checks the type of the values.
+     */
+    pub range_node_link: bool,
+    /**
+     * Whether to mark all the property shapes generated
+     * from qualified restrictions (`owl:someValuesFrom`,
+     * `owl:qualifiedCardinality` and friends)
+     * with `sh:qualifiedValueShapesDisjoint true`,
+     * so a value conforming to the qualified value shape of one of them
+     * is not also counted for the ones of its siblings.
+     * This is stricter than OWL, which allows such overlap.
+     */
+    pub qualified_value_shapes_disjoint: bool,
+    /**
+     * Whether an `owl:FunctionalProperty`
+     * gets `sh:maxCount 1` on its property shape.
+     */
+    pub functional_property: bool,
+    /**
+     * Whether an `owl:InverseFunctionalProperty` gets an additional shape
+     * (`<property>InverseShape`), targeting all its values,
+     * with `sh:path [ sh:inversePath <property> ]` and `sh:maxCount 1`.
+     */
+    pub inverse_functional_property: bool,
+    /**
+     * How sub-properties (`rdfs:subPropertyOf`) inherit
+     * the domains and ranges of their (transitive) super-properties.
+     * According to RDFS, they apply to the sub-properties as well,
+     * but repeating them might be noisy
+     * if the sub-property already is more specific.
+     */
+    pub sub_property_inheritance: EnumMap<RDProperty, Inheritance>,
+    /**
+     * Whether the property shape of a super-property gets
+     * `sh:path [ sh:alternativePath ( <super> <sub1> <sub2> ... ) ]`
+     * instead of `sh:path <super>`, so the values of its sub-properties
+     * count as values of the super-property as well,
+     * as they would after RDFS inference.
+     */
+    pub sub_property_alternative_path: bool,
+    /**
+     * How the two properties of an `owl:inverseOf` pair
+     * get their domain and range from each other:
+     * the domains of one are the ranges of the other, and vice versa.
+     */
+    pub inverse_inheritance: EnumMap<RDProperty, Inheritance>,
+    /**
+     * Whether to additionally add a property shape
+     * with `sh:path [ sh:inversePath <property> ]`
+     * to the node shapes of the range classes of each property
+     * that is part of an `owl:inverseOf` pair,
+     * constraining the class of the subjects pointing to them.
+     */
+    pub inverse_path: bool,
+    /**
+     * Whether to translate the property characteristics
+     * `owl:SymmetricProperty`, `owl:AsymmetricProperty`,
+     * `owl:IrreflexiveProperty` and `owl:TransitiveProperty`
+     * into SPARQL-based constraints (`sh:sparql`),
+     * in a node shape `<property>SparqlShape` per property.
+     * These are not part of SHACL Core,
+     * and thus require a validator that supports SHACL-SPARQL.
+     */
+    pub sparql_constraints: bool,
+    /**
+     * Whether each class gets its own copy of the property shapes
+     * of the properties it is the domain of,
+     * so that e.g. restrictions of `ex:Person` on `ex:name`
+     * end up in the same shape as the domain and range of `ex:name`,
+     * without affecting the other classes using `ex:name`.
+     */
+    pub property_shapes: PropertyShapes,
+    /**
+     * How the node shape of a class gets the constraints
+     * of the shapes of its super-classes (`rdfs:subClassOf`),
+     * so that e.g. validating an `ex:Employee`
+     * also checks the `ex:Person` constraints.
+     */
+    pub class_hierarchy: ClassHierarchy,
+    /**
+     * How disjoint classes get converted,
+     * so that e.g. data typed both as `ex:Person` and `ex:Organization`
+     * is reported.
+     */
+    pub disjoint_classes: DisjointClasses,
+    /**
+     * Whether to generate closed node shapes (`sh:closed true`),
+     * which only allow the properties that have a property shape
+     * in the node shape itself, or in the shapes related to it
+     * through the class hierarchy.
+     * `rdf:type` and [`Self::ignored_properties`] are always allowed.
+     */
+    pub closed: bool,
+    /**
+     * Properties that are always allowed in closed node shapes
+     * (see [`Self::closed`]), in addition to `rdf:type`,
+     * e.g. annotation properties like `rdfs:label` or `rdfs:comment`.
+     */
+    pub ignored_properties: Vec<NamedNode>,
+    /**
+     * How to name the generated shapes.
+     * By default, they get the IRI of the class or property
+     * they are generated for, with the suffix `Shape`.
+     */
+    pub shape_naming: ShapeNaming,
+    /**
+     * What to do if the IRI of a generated shape
+     * is already used in the source ontology,
+     * or minted for another generated shape as well
+     * (e.g. the inverse shape of `ex:p` and the shape of `ex:pInverse`),
+     * which merges the two.
+     */
+    pub shape_name_collision: OdityHandling,
+    /**
+     * The RDF serialization of the input ontologies;
+     * `None` to infer it from the extension of each file,
+     * or - failing that - from its content.
+     */
+    pub input_format: Option<InputFormat>,
+    /**
+     * The named graphs to load from N-Quads and `TriG` input ontologies,
+     * in addition to their default graph;
+     * all of them if empty.
+     * The loaded graphs are merged into a single graph before conversion.
+     */
+    pub input_graphs: Vec<NamedNode>,
+    /**
+     * The RDF serialization to write the shapes in;
+     * `None` to infer it from the extension of the output file,
+     * falling back to Turtle.
+     */
+    pub output_format: Option<OutputFormat>,
+    /**
+     * The named graph to put the shapes into,
+     * for the output formats supporting named graphs
+     * (N-Quads, `TriG` and JSON-LD).
+     */
+    pub shapes_graph: Option<NamedNode>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            and_list_detected: EnumMap::default(),
+            style_mix_property: EnumMap::default(),
+            style_mix_ontology: EnumMap::default(),
+            unsupported_construct: OdityHandling::default(),
+            cardinality_conflict: OdityHandling::default(),
+            range_node_link: false,
+            qualified_value_shapes_disjoint: false,
+            functional_property: true,
+            inverse_functional_property: true,
+            sub_property_inheritance: EnumMap::default(),
+            sub_property_alternative_path: false,
+            inverse_inheritance: EnumMap::default(),
+            inverse_path: false,
+            sparql_constraints: false,
+            property_shapes: PropertyShapes::default(),
+            class_hierarchy: ClassHierarchy::default(),
+            disjoint_classes: DisjointClasses::default(),
+            closed: false,
+            ignored_properties: Vec::new(),
+            shape_naming: ShapeNaming::default(),
+            shape_name_collision: OdityHandling::default(),
+            input_format: None,
+            input_graphs: Vec::new(),
+            output_format: None,
+            shapes_graph: None,
+        }
+    }
 }
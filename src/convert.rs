@@ -3,15 +3,16 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use std::collections::HashSet;
-use std::fs::File;
 
 use enum_map::EnumMap;
 use once_cell::sync::Lazy;
-use oxigraph::io::GraphFormat;
-use oxigraph::model::vocab::rdf;
+use oxigraph::model::vocab::{rdf, rdfs};
 
 use const_format::concatcp;
-use oxigraph::model::{GraphName, GraphNameRef, NamedNodeRef, Quad, Term};
+use oxigraph::model::{
+    BlankNode, GraphName, GraphNameRef, Literal, NamedNode, NamedNodeRef, Quad, QuadRef,
+    SubjectRef, Term, Triple,
+};
 use oxigraph::sparql::Query;
 use oxigraph::sparql::QueryResults;
 use oxigraph::sparql::QuerySolution;
@@ -20,19 +21,20 @@ use oxigraph::store::Store;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+use crate::cardinality::Cardinality;
 use crate::config::Config;
 use crate::config::OdityHandling;
 use crate::config::RDProperty;
+use crate::error::ConversionError;
+use crate::naming::ShapeKind;
 use crate::vocab::{basics, owl, sh};
 
-type Error = Box<dyn std::error::Error + Sync + Send>;
-type Res<O> = Result<O, Error>;
+type Res<O> = Result<O, ConversionError>;
 
 const QUERY_PRELUDE: &str = r"
 #@base           <https://w3id.org/valueflows>
 #PREFIX vf:      <#>
 PREFIX vf:      <https://w3id.org/valueflows#>
-PREFIX vfs:     <https://w3id.org/valueflows/shapes#>
 PREFIX rdf:     <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
 PREFIX owl:     <http://www.w3.org/2002/07/owl#>
 PREFIX rdfs:    <http://www.w3.org/2000/01/rdf-schema#>
@@ -58,11 +60,7 @@ macro_rules! query_parser {
     ($const:ident, $query_str:ident) => {
         pub static $const: Lazy<Query> = Lazy::new(|| {
             let query_str = concatcp!(QUERY_PRELUDE, '\n', $query_str);
-            std::fs::write(
-                &format!("target/{}.sparql.txt", stringify!($const)),
-                query_str,
-            )
-            .expect("Failed to write query to file!");
+            log::trace!("Query {}:\n{query_str}", stringify!($const));
             Query::parse(query_str, None).unwrap()
         });
     };
@@ -76,10 +74,21 @@ WHERE {
             rdfs:Class owl:Class
         }
         ?s rdf:type ?t
+        # Anonymous class expressions are converted where they are used
+        FILTER ( isIRI(?s) )
     }
     UNION
     {
         ?s rdfs:subClassOf ?o
+        FILTER ( isIRI(?s) )
+    }
+    UNION
+    {
+        ?s owl:equivalentClass ?o .
+        FILTER ( isIRI(?s) )
+        # Datatypes may be defined through owl:equivalentClass as well
+        FILTER NOT EXISTS { ?s rdf:type rdfs:Datatype }
+        FILTER NOT EXISTS { ?o rdf:type rdfs:Datatype }
     }
 }
 ORDER BY ?s
@@ -106,19 +115,30 @@ query_parser!(Q_CLASSES, QS_CLASSES);
 // "#;
 const QS_PROPERTIES: &str = r"
 SELECT
-    ?s ?t ?label ?description ?cardinality ?maxCardinality ?minCardinality
+    ?s ?label ?description
     # HACK Due to this bug in OxiGraph, we first have to use the STR() function for the GROUP_CONCAT() argument: <https://github.com/oxigraph/oxigraph/issues/297>
+    ( GROUP_CONCAT( DISTINCT STR(?cardinality); separator=',' ) as ?cardinalityList )
+    ( GROUP_CONCAT( DISTINCT STR(?minCardinality); separator=',' ) as ?minCardinalityList )
+    ( GROUP_CONCAT( DISTINCT STR(?maxCardinality); separator=',' ) as ?maxCardinalityList )
     ( GROUP_CONCAT( DISTINCT STR(?domain); separator=',' ) as ?domainAndList )
     ( GROUP_CONCAT( DISTINCT STR(?range); separator=',' ) as ?rangeAndList )
     ( GROUP_CONCAT( DISTINCT STR(?domainIncludes); separator=',' ) as ?domainIncludesList )
     ( GROUP_CONCAT( DISTINCT STR(?rangeIncludes); separator=',' ) as ?rangeIncludesList )
     ( GROUP_CONCAT( DISTINCT STR(?domainOred); separator=',' ) as ?domainOredList )
-    ( GROUP_CONCAT( DISTINCT STR(?rangeOred); separator=',' ) as ?rangeOredList )
 WHERE {
-    VALUES ?t {
-        rdf:Property owl:ObjectProperty owl:DatatypeProperty owl:AnnotationProperty
-    } .
-    ?s rdf:type ?t .
+    {
+        VALUES ?t {
+            rdf:Property owl:ObjectProperty owl:DatatypeProperty owl:AnnotationProperty
+            owl:FunctionalProperty owl:InverseFunctionalProperty
+            owl:SymmetricProperty owl:AsymmetricProperty
+            owl:IrreflexiveProperty owl:TransitiveProperty
+        } .
+        ?s rdf:type ?t .
+    }
+    UNION
+    {
+        ?s rdfs:subPropertyOf ?superProperty .
+    }
     OPTIONAL {
         ?s rdfs:label ?label .
     } .
@@ -143,19 +163,19 @@ WHERE {
     } .
     OPTIONAL {
         ?s rdfs:range ?range .
-        OPTIONAL {
-            ?range owl:unionOf ?rangeOredUnion .
-            ?rangeOredUnion rdf:rest*/rdf:first ?rangeOred .
-        }
+        # All ranges apply, so blank ones (e.g. unions) are converted
+        # each on their own, see `anonymous_range`
     } .
     OPTIONAL {
         ?s schema:domainIncludes | dcam:domainIncludes | dcid:domainIncludes ?domainIncludes .
     } .
     OPTIONAL {
         ?s schema:rangeIncludes | dcam:rangeIncludes | dcid:rangeIncludes ?rangeIncludes .
+        # Named and blank ones together make up a single `sh:or`,
+        # see `anonymous_range`; here we only note the style
     } .
 }
-GROUP BY ?s ?t ?label ?description ?cardinality ?maxCardinality ?minCardinality
+GROUP BY ?s ?label ?description
 ORDER BY ?s
 ";
 query_parser!(Q_PROPERTIES, QS_PROPERTIES);
@@ -190,33 +210,353 @@ macro_rules! ins_opt {
     };
 }
 
-macro_rules! type2shape {
-    ($shape_var:ident, $orig:expr) => {
-        let subj_iri = if let Term::NamedNode(nn) = $orig {
-            nn.as_str()
-        } else {
-            panic!("Only named-node properties subjects are supported!");
+mod anonymous_range;
+mod characteristics;
+mod class_hierarchy;
+mod closed;
+mod datatype_restriction;
+mod disjoint;
+mod equivalent_class;
+mod inverse;
+mod local_property;
+mod one_of;
+mod property_pair;
+mod restriction;
+mod shape_names;
+mod sub_property;
+
+fn new_iri(iri: String) -> Res<NamedNode> {
+    NamedNode::new(iri.clone()).map_err(|source| ConversionError::InvalidIri { iri, source })
+}
+
+fn get_required<'a>(sol: &'a QuerySolution, var: &'static str) -> Res<&'a Term> {
+    sol.get(var).ok_or(ConversionError::MissingBinding { var })
+}
+
+/**
+ * Splits the value of a `GROUP_CONCAT`ed SPARQL variable into its parts.
+ * An unbound variable results in an empty list.
+ */
+fn get_list<'a>(sol: &'a QuerySolution, subj: &Term, var: &Variable) -> Res<Vec<&'a str>> {
+    match sol.get(var) {
+        None => Ok(Vec::new()),
+        Some(Term::Literal(lit)) => Ok(lit
+            .value()
+            .split(',')
+            .filter(|part| !part.is_empty())
+            .collect()),
+        Some(other @ (Term::NamedNode(_) | Term::BlankNode(_) | Term::Triple(_))) => {
+            Err(ConversionError::UnexpectedBinding {
+                iri: subj.to_string(),
+                var: var.as_str().to_owned(),
+                value: other.to_string(),
+                expected: "a string literal",
+            })
+        }
+    }
+}
+
+/**
+ * Ensures that the class or property `orig` is a named node.
+ * `role` describes what `orig` is, and is only used for error reporting.
+ */
+fn as_named<'a>(orig: &'a Term, role: &'static str) -> Res<NamedNodeRef<'a>> {
+    match orig {
+        Term::NamedNode(nn) => Ok(nn.as_ref()),
+        Term::BlankNode(_) => Err(ConversionError::BlankNodeSubject {
+            node: orig.to_string(),
+            role,
+        }),
+        Term::Literal(_) | Term::Triple(_) => Err(ConversionError::UnsupportedConstruct {
+            iri: orig.to_string(),
+            what: format!("{role} that is neither a named nor a blank node"),
+        }),
+    }
+}
+
+/**
+ * Mints the IRI of the shape for the class or property `orig`.
+ * `role` describes what `orig` is, and is only used for error reporting.
+ */
+fn type2shape(config: &Config, orig: &Term, role: &'static str) -> Res<NamedNode> {
+    let shape = shape_iri(config, as_named(orig, role)?, ShapeKind::Main)?;
+    log::info!("Shape:    {shape}");
+    Ok(shape)
+}
+
+/**
+ * Mints the IRI of the shape of kind `kind` for the class or property `orig`,
+ * according to [`Config::shape_naming`].
+ */
+fn shape_iri(config: &Config, orig: NamedNodeRef, kind: ShapeKind) -> Res<NamedNode> {
+    new_iri(config.shape_naming.mint(orig, kind))
+}
+
+/**
+ * Replaces `from` with `to` in all the triples of `store`,
+ * both as subject and as object.
+ */
+fn rename_node(store: &Store, from: NamedNodeRef, to: SubjectRef) -> Res<()> {
+    let about = store
+        .quads_for_pattern(
+            Some(from.into()),
+            None,
+            None,
+            Some(GraphNameRef::DefaultGraph),
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    for quad in about {
+        store.remove(&quad)?;
+        ins!(store, to, quad.predicate, quad.object);
+    }
+    let pointing = store
+        .quads_for_pattern(
+            None,
+            None,
+            Some(from.into()),
+            Some(GraphNameRef::DefaultGraph),
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    for quad in pointing {
+        store.remove(&quad)?;
+        ins!(
+            store,
+            quad.subject.clone(),
+            quad.predicate,
+            Term::from(to.into_owned())
+        );
+    }
+    Ok(())
+}
+
+/**
+ * All the objects of `subj` with the predicate `pred`.
+ */
+fn objects(store: &Store, subj: SubjectRef, pred: NamedNodeRef) -> Res<Vec<Term>> {
+    store
+        .quads_for_pattern(
+            Some(subj),
+            Some(pred),
+            None,
+            Some(GraphNameRef::DefaultGraph),
+        )
+        .map(|quad| Ok(quad?.object))
+        .collect()
+}
+
+fn as_subject(term: &Term) -> Option<SubjectRef<'_>> {
+    match term {
+        Term::NamedNode(node) => Some(node.into()),
+        Term::BlankNode(node) => Some(node.into()),
+        Term::Literal(_) | Term::Triple(_) => None,
+    }
+}
+
+/**
+ * Reads the items of the RDF list starting at `head`.
+ */
+fn read_list(store: &Store, head: &Term) -> Res<Vec<Term>> {
+    let mut items = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current = head.clone();
+    while let Some(node) = as_subject(&current).filter(|_| visited.insert(current.clone())) {
+        items.extend(objects(store, node, rdf::FIRST)?);
+        let Some(rest) = objects(store, node, rdf::REST)?.into_iter().next() else {
+            break;
         };
+        current = rest;
+    }
+    Ok(items)
+}
 
-        let shape_iri = format!("{subj_iri}Shape");
-        let $shape_var = NamedNodeRef::new(&shape_iri)?;
-        log::info!("Shape:    {}", $shape_var);
+/**
+ * Inserts `items` as an RDF list (`rdf:first`/`rdf:rest`),
+ * and returns its head, which is `rdf:nil` if there are no items.
+ */
+fn insert_list<I>(store_shacl: &Store, items: I) -> Res<Term>
+where
+    I: IntoIterator<Item = Term>,
+    I::IntoIter: DoubleEndedIterator,
+{
+    let mut head: Term = rdf::NIL.into_owned().into();
+    for item in items.into_iter().rev() {
+        let node = BlankNode::default();
+        ins!(store_shacl, node.as_ref(), rdf::FIRST, item);
+        ins!(store_shacl, node.as_ref(), rdf::REST, head);
+        head = node.into();
+    }
+    Ok(head)
+}
+
+/**
+ * Copies `term`, including - recursively - the triples about it,
+ * if it is a blank node, so the copy does not share any blank nodes.
+ */
+fn copy_term(store_shacl: &Store, term: &Term) -> Res<Term> {
+    let Term::BlankNode(orig) = term else {
+        return Ok(term.clone());
     };
+    let copy = BlankNode::default();
+    for (predicate, object) in objects_of(store_shacl, orig.into())? {
+        let object = copy_term(store_shacl, &object)?;
+        ins!(store_shacl, copy.as_ref(), predicate, object);
+    }
+    Ok(copy.into())
+}
+
+/**
+ * All the predicates and objects of `subj`.
+ */
+fn objects_of(store_shacl: &Store, subj: SubjectRef) -> Res<Vec<(NamedNode, Term)>> {
+    store_shacl
+        .quads_for_pattern(Some(subj), None, None, Some(GraphNameRef::DefaultGraph))
+        .map(|quad| {
+            let quad = quad?;
+            Ok((quad.predicate, quad.object))
+        })
+        .collect()
+}
+
+/**
+ * The property a property shape with the path `path` is about,
+ * which is the first one of an `sh:alternativePath`.
+ */
+fn path_property(store_shacl: &Store, path: &Term) -> Res<Option<NamedNode>> {
+    match path {
+        Term::NamedNode(property) => Ok(Some(property.clone())),
+        Term::BlankNode(path_bn) => {
+            for alternatives in objects(store_shacl, path_bn.into(), sh::ALTERNATIVE_PATH)? {
+                if let Some(Term::NamedNode(property)) =
+                    read_list(store_shacl, &alternatives)?.into_iter().next()
+                {
+                    return Ok(Some(property));
+                }
+            }
+            Ok(None)
+        }
+        Term::Literal(_) | Term::Triple(_) => Ok(None),
+    }
+}
+
+/// The constraints of a property shape that make up its range.
+const RANGE_CONSTRAINTS: [NamedNodeRef<'static>; 16] = [
+    sh::CLASS,
+    sh::DATA_TYPE,
+    sh::NODE_KIND,
+    sh::NODE,
+    sh::IN,
+    sh::MIN_INCLUSIVE,
+    sh::MAX_INCLUSIVE,
+    sh::MIN_EXCLUSIVE,
+    sh::MAX_EXCLUSIVE,
+    sh::MIN_LENGTH,
+    sh::MAX_LENGTH,
+    sh::PATTERN,
+    sh::FLAGS,
+    sh::AND,
+    sh::OR,
+    sh::NOT,
+];
+
+/**
+ * Returns the shape generated for the property `prop`,
+ * if there is one.
+ */
+fn property_shape(
+    store_shacl: &Store,
+    config: &Config,
+    prop: &NamedNode,
+) -> Res<Option<NamedNode>> {
+    let shape = type2shape(config, &prop.clone().into(), "property")?;
+    Ok(has_type(store_shacl, &shape, sh::PROPERTY_SHAPE)?.then_some(shape))
+}
+
+/**
+ * The node shapes that have `shape` as one of their property shapes,
+ * which are the shapes of the domains of the property.
+ */
+fn domains(store_shacl: &Store, shape: &NamedNode) -> Res<Vec<Term>> {
+    store_shacl
+        .quads_for_pattern(
+            None,
+            Some(sh::PROPERTY),
+            Some(shape.as_ref().into()),
+            Some(GraphNameRef::DefaultGraph),
+        )
+        .map(|quad| Ok(quad?.subject.into()))
+        .collect()
+}
+
+/**
+ * The range constraints of `shape`.
+ */
+fn ranges(store_shacl: &Store, shape: &NamedNode) -> Res<Vec<(NamedNodeRef<'static>, Term)>> {
+    let mut ranges = Vec::new();
+    for pred in RANGE_CONSTRAINTS {
+        for quad in store_shacl.quads_for_pattern(
+            Some(shape.as_ref().into()),
+            Some(pred),
+            None,
+            Some(GraphNameRef::DefaultGraph),
+        ) {
+            ranges.push((pred, quad?.object));
+        }
+    }
+    Ok(ranges)
+}
+
+/**
+ * Which of domain and range the property shape `shape` specifies itself.
+ */
+fn own_specs(store_shacl: &Store, shape: &NamedNode) -> Res<EnumMap<RDProperty, bool>> {
+    let has_range = !ranges(store_shacl, shape)?.is_empty();
+    let has_domain = !domains(store_shacl, shape)?.is_empty();
+    Ok(EnumMap::from_fn(|prop| match prop {
+        RDProperty::Range => has_range,
+        RDProperty::Domain => has_domain,
+    }))
+}
+
+/**
+ * Handles the result of converting a single axiom (e.g. a class or a property).
+ * Axiom local errors are handled according to [`Config::unsupported_construct`],
+ * so one strange axiom does not have to abort the whole conversion;
+ * all other errors are passed on.
+ */
+fn handle_axiom_result<T>(config: &Config, subj: &Term, res: Res<T>) -> Res<Option<T>> {
+    match res {
+        Ok(val) => Ok(Some(val)),
+        Err(err) if err.is_axiom_local() => match config.unsupported_construct {
+            OdityHandling::Ignore => Ok(None),
+            OdityHandling::Warn => {
+                log::warn!("Skipping (parts of) {subj}: {err}");
+                Ok(None)
+            }
+            OdityHandling::Error => Err(err),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+fn convert_class(store_shacl: &Store, config: &Config, subj: &Term) -> Res<()> {
+    let shape = type2shape(config, subj, "class")?;
+    let shape = shape.as_ref();
+
+    ins!(store_shacl, shape, rdf::TYPE, sh::NODE_SHAPE);
+    ins!(store_shacl, shape, sh::TARGET_CLASS, subj.clone());
+
+    Ok(())
 }
 
-fn convert_classes(store_owl: &Store, store_shacl: &mut Store) -> Res<()> {
+fn convert_classes(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
     log::info!("Converting classes ...");
     if let QueryResults::Solutions(solutions) = store_owl.query(Q_CLASSES.to_owned())? {
         for sol_res in solutions {
             let sol = sol_res?;
-            let subj = sol.get("s").unwrap();
+            let subj = get_required(&sol, "s")?;
             log::info!("Class: {subj}");
 
-            type2shape!(shape, subj);
-
-            ins!(store_shacl, shape, rdf::TYPE, sh::NODE_SHAPE);
-            ins!(store_shacl, shape, sh::TARGET_CLASS, subj.clone());
-            ins!(store_shacl, shape, sh::CLOSED, *basics::BOOL_FALSE);
+            handle_axiom_result(config, subj, convert_class(store_shacl, config, subj))?;
         }
         log::info!("Converting classes - done.");
     } else {
@@ -258,99 +598,158 @@ impl ListCollectionMethod {
     }
 }
 
+fn has_type<'a>(
+    store: &Store,
+    subj: impl Into<SubjectRef<'a>>,
+    typ: NamedNodeRef<'a>,
+) -> Res<bool> {
+    Ok(store.contains(QuadRef::new(
+        subj,
+        rdf::TYPE,
+        typ,
+        GraphNameRef::DefaultGraph,
+    ))?)
+}
+
+/**
+ * Whether `range` should be treated as a datatype, rather than a class.
+ */
+fn is_datatype(store_owl: &Store, range: NamedNodeRef) -> Res<bool> {
+    Ok(range.as_str().starts_with(basics::NS_BASE_XSD)
+        || range == rdf::LANG_STRING
+        || range == rdf::HTML
+        || range == rdf::XML_LITERAL
+        || has_type(store_owl, range, rdfs::DATATYPE)?)
+}
+
+/**
+ * Constrains the values of the property shape `shape`
+ * to be of the class or datatype `range`.
+ */
+fn insert_range(
+    store_owl: &Store,
+    store_shacl: &Store,
+    shape: SubjectRef,
+    config: &Config,
+    range: NamedNodeRef,
+    is_datatype_prop: bool,
+) -> Res<()> {
+    if range == owl::THING || range == rdfs::RESOURCE {
+        // Anything goes
+    } else if range == rdfs::LITERAL {
+        ins!(store_shacl, shape, sh::NODE_KIND, sh::LITERAL);
+    } else if one_of::insert_one_of(store_owl, store_shacl, shape, range.into())? {
+        // An enumeration; its members need not be explicitly typed
+    } else if datatype_restriction::insert_named_datatype_restriction(
+        store_owl,
+        store_shacl,
+        shape,
+        range,
+    )? {
+        // A restriction of another datatype, which is what the values are typed as
+    } else if is_datatype_prop || is_datatype(store_owl, range)? {
+        ins!(store_shacl, shape, sh::DATA_TYPE, range);
+    } else {
+        ins!(store_shacl, shape, sh::CLASS, range);
+        if config.range_node_link {
+            let range_shape = type2shape(config, &range.into_owned().into(), "range")?;
+            // Only link to shapes we actually generated
+            if has_type(store_shacl, &range_shape, sh::NODE_SHAPE)? {
+                ins!(store_shacl, shape, sh::NODE, range_shape.as_ref());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_lines)]
 fn convert_property_range_or_domain(
-    store_shacl: &mut Store,
+    store_owl: &Store,
+    store_shacl: &Store,
     shape: NamedNodeRef,
     config: &Config,
     sol: &QuerySolution,
     prop: RDProperty,
+    is_datatype_prop: bool,
 ) -> Res<HashSet<ListCollectionMethod>> {
     let prop_str = prop.to_str();
+    let subj = get_required(sol, "s")?;
     // let mut used = EnumMap::from_fn(|_| HashSet::new());
     let mut used = HashSet::new();
-    let is_dataype_prop = if let Term::NamedNode(nn) = sol
-        .get("t")
-        .expect("Required SPARQL var ?t (rdfs:type) missing")
-    {
-        nn.eq(&owl::DATATYPE_PROPERTY)
-    } else {
-        panic!("Only named-node properties subjects are supported as `rdfs:type` objects!");
-    };
     for collection_method in ListCollectionMethod::iter() {
-        let list_var = Variable::new(format!("{prop_str}{}", collection_method.to_var_postfix()))?;
-        if let Some(list) = sol.get(&list_var) {
-            match list {
-                Term::Literal(lit) => {
-                    let lit_str = lit.as_ref().value();
-                    if !lit_str.is_empty() {
-                        used.insert(collection_method);
-                        log::info!("    {list_var}:");
-                        let parts = lit_str.split(',').collect::<Vec<_>>();
-                        let num_parts = parts.len();
-                        for part in parts {
-                            log::info!("      - {part}:");
-                            // type2shape!(part_shape, part);
-                            let part_shape_iri = format!("{part}Shape");
-                            let part_shape = NamedNodeRef::new(&part_shape_iri)?;
-                            if collection_method.is_and() {
-                                let is_list = num_parts > 1;
-                                if is_list {
-                                    match config.and_list_detected[prop] {
-                                        OdityHandling::Ignore => {
-                                            continue;
-                                        }
-                                        OdityHandling::Warn => {
-                                            log::warn!("And list detected for property {prop_str}; this is not supported in our to-SHACL converter.");
-                                        }
-                                        OdityHandling::Error => {
-                                            return Err(format!("And list detected for property {prop_str}; this is not supported in our to-SHACL converter.").into());
-                                        }
-                                    }
-                                }
-                                match prop {
-                                    RDProperty::Range => {
-                                        if is_dataype_prop {
-                                            if num_parts == 1 {
-                                                ins!(store_shacl, shape, sh::DATA_TYPE, part_shape);
-                                            } else {
-                                                todo!(); // TODO
-                                            }
-                                        } else {
-                                            if num_parts == 1 {
-                                                ins!(store_shacl, shape, sh::CLASS, part_shape);
-                                            } else {
-                                                todo!(); // TODO
-                                            }
-                                        }
-                                    }
-                                    RDProperty::Domain => {
-                                        if num_parts == 1 {
-                                            ins!(store_shacl, part_shape, sh::PROPERTY, shape);
-                                        } else {
-                                            todo!(); // TODO
-                                        }
-                                    }
-                                }
-                            } else {
-                                match prop {
-                                    RDProperty::Range => {
-                                        if is_dataype_prop {
-                                            ins!(store_shacl, shape, sh::DATA_TYPE, part_shape);
-                                        } else {
-                                            ins!(store_shacl, shape, sh::CLASS, part_shape);
-                                        }
-                                    }
-                                    RDProperty::Domain => {
-                                        ins!(store_shacl, part_shape, sh::PROPERTY, shape);
-                                    }
-                                }
-                            }
-                        }
-                    }
+        let list_var =
+            Variable::new_unchecked(format!("{prop_str}{}", collection_method.to_var_postfix()));
+        let parts = get_list(sol, subj, &list_var)?;
+        if parts.is_empty() {
+            continue;
+        }
+        used.insert(collection_method);
+        log::info!("    {list_var}:");
+        if collection_method.is_and() && parts.len() > 1 {
+            let msg = format!(
+                "And list detected for {prop_str} of property {subj}, \
+                meaning that all of these have to apply: {}",
+                parts.join(", ")
+            );
+            match config.and_list_detected[prop] {
+                OdityHandling::Ignore => {}
+                OdityHandling::Warn => log::warn!("{msg}"),
+                OdityHandling::Error => {
+                    return Err(ConversionError::Oddity {
+                        iri: Some(subj.to_string()),
+                        msg,
+                    });
                 }
-                // Term::Triple(_) => panic!("RDF triple as or-ed/unionOf-listed object/value for rdfs:domain is not (yet) supported."),
-                Term::NamedNode(_) | Term::BlankNode(_) | Term::Triple(_) => {
-                    panic!("Type for SPARQL variable {list_var} should be Literal(string).")
+            }
+        }
+        if prop == RDProperty::Range && collection_method == ListCollectionMethod::Includes {
+            // All alternatives, including blank ones, see `anonymous_range`
+            continue;
+        }
+        // Adding the property shape to the shape of each domain class
+        // fits the semantics of all kinds of lists,
+        // so only multiple ranges need special care.
+        if prop == RDProperty::Range && parts.len() > 1 {
+            let logical = if collection_method.is_and() {
+                sh::AND
+            } else {
+                sh::OR
+            };
+            // GROUP_CONCAT does not guarantee any order
+            let mut parts = parts;
+            parts.sort_unstable();
+            let parts = parts
+                .into_iter()
+                .map(|part| Ok(new_iri(part.to_owned())?.into()))
+                .collect::<Res<Vec<Term>>>()?;
+            anonymous_range::insert_logical(
+                store_owl,
+                store_shacl,
+                config,
+                shape.into(),
+                logical,
+                &parts,
+                is_datatype_prop,
+            )?;
+            continue;
+        }
+        for part in parts {
+            log::info!("      - {part}:");
+            let part = new_iri(part.to_owned())?;
+            match prop {
+                RDProperty::Range => {
+                    insert_range(
+                        store_owl,
+                        store_shacl,
+                        shape.into(),
+                        config,
+                        part.as_ref(),
+                        is_datatype_prop,
+                    )?;
+                }
+                RDProperty::Domain => {
+                    let part_shape = type2shape(config, &part.into(), "domain")?;
+                    ins!(store_shacl, part_shape.as_ref(), sh::PROPERTY, shape);
                 }
             }
         }
@@ -358,7 +757,7 @@ fn convert_property_range_or_domain(
     let action = config.style_mix_property[prop];
     if !action.ignore() && (used.len() > 1) {
         let msg = format!(
-            "Mixed styles of {} definitions in Property: {}",
+            "Mixed styles of {} definitions in Property {subj}: {}",
             prop_str,
             used.iter()
                 .map(|style| format!("{style:?}"))
@@ -366,45 +765,176 @@ fn convert_property_range_or_domain(
                 .join(", ")
         );
         match action {
-            OdityHandling::Error => return Err(msg.into()),
+            OdityHandling::Error => {
+                return Err(ConversionError::Oddity {
+                    iri: Some(subj.to_string()),
+                    msg,
+                })
+            }
             OdityHandling::Warn => log::warn!("{msg}"),
-            OdityHandling::Ignore => panic!("This should never happen"),
+            OdityHandling::Ignore => unreachable!(),
         }
     }
 
     Ok(used)
 }
 
-fn convert_properties(store_owl: &Store, store_shacl: &mut Store, config: &Config) -> Res<()> {
+fn parse_cardinality(subj: &Term, value: &str) -> Res<u64> {
+    value
+        .parse()
+        .map_err(|_err| ConversionError::UnsupportedConstruct {
+            iri: subj.to_string(),
+            what: format!("cardinality '{value}', which is not a non-negative integer"),
+        })
+}
+
+fn parse_cardinalities(sol: &QuerySolution, subj: &Term, var: &str) -> Res<Vec<u64>> {
+    get_list(sol, subj, &Variable::new_unchecked(var))?
+        .into_iter()
+        .map(|value| parse_cardinality(subj, value))
+        .collect()
+}
+
+/**
+ * Inserts `sh:minCount` and `sh:maxCount` for the given cardinality,
+ * always typed as `xsd:integer`, as required by SHACL.
+ * A minimum of zero is left out, as it does not constrain anything.
+ */
+fn insert_cardinality(store_shacl: &Store, shape: SubjectRef, cardinality: Cardinality) -> Res<()> {
+    if let Some(min) = cardinality.min.filter(|min| *min > 0) {
+        ins!(store_shacl, shape, sh::MIN_COUNT, Literal::from(min));
+    }
+    if let Some(max) = cardinality.max {
+        ins!(store_shacl, shape, sh::MAX_COUNT, Literal::from(max));
+    }
+    Ok(())
+}
+
+/**
+ * Combines cardinalities as done by [`Cardinality::combine`],
+ * handling conflicts according to [`Config::cardinality_conflict`].
+ * `subj_desc` describes what the cardinalities belong to,
+ * for error reporting.
+ */
+fn combine_cardinalities(
+    config: &Config,
+    subj: &Term,
+    subj_desc: &str,
+    exact: &[u64],
+    min: &[u64],
+    max: &[u64],
+) -> Res<Cardinality> {
+    Ok(match Cardinality::combine(exact, min, max) {
+        Ok(cardinality) => cardinality,
+        Err((combined, conflict)) => {
+            let msg = format!("{subj_desc} has {conflict}");
+            match config.cardinality_conflict {
+                OdityHandling::Ignore => {}
+                OdityHandling::Warn => log::warn!("{msg}"),
+                OdityHandling::Error => {
+                    return Err(ConversionError::Oddity {
+                        iri: Some(subj.to_string()),
+                        msg,
+                    })
+                }
+            }
+            combined
+        }
+    })
+}
+
+fn convert_cardinalities(
+    store_shacl: &Store,
+    shape: NamedNodeRef,
+    config: &Config,
+    sol: &QuerySolution,
+    subj: &Term,
+    functional: bool,
+) -> Res<()> {
+    let exact = parse_cardinalities(sol, subj, "cardinalityList")?;
+    let min = parse_cardinalities(sol, subj, "minCardinalityList")?;
+    let mut max = parse_cardinalities(sol, subj, "maxCardinalityList")?;
+    if functional {
+        max.push(1);
+    }
+    let subj_desc = format!("Property {subj}");
+    let cardinality = combine_cardinalities(config, subj, &subj_desc, &exact, &min, &max)?;
+    insert_cardinality(store_shacl, shape.into(), cardinality)
+}
+
+/**
+ * Creates a shape that allows each value of the inverse-functional property `subj`
+ * to be the value of only a single subject.
+ */
+fn convert_inverse_functional(store_shacl: &Store, config: &Config, subj: NamedNodeRef) -> Res<()> {
+    let shape = shape_iri(config, subj, ShapeKind::Inverse)?;
+    let shape = shape.as_ref();
+    let path = BlankNode::default();
+
+    ins!(store_shacl, shape, rdf::TYPE, sh::PROPERTY_SHAPE);
+    ins!(store_shacl, shape, sh::TARGET_OBJECTS_OF, subj);
+    ins!(store_shacl, shape, sh::PATH, path.as_ref());
+    ins!(store_shacl, path.as_ref(), sh::INVERSE_PATH, subj);
+    ins!(store_shacl, shape, sh::MAX_COUNT, Literal::from(1_u64));
+
+    Ok(())
+}
+
+fn convert_property(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    sol: &QuerySolution,
+    subj: &Term,
+) -> Res<EnumMap<RDProperty, HashSet<ListCollectionMethod>>> {
+    let shape = type2shape(config, subj, "property")?;
+    let shape = shape.as_ref();
+    let subj_nn = as_named(subj, "property")?;
+    let is_datatype_prop = has_type(store_owl, subj_nn, owl::DATATYPE_PROPERTY)?;
+    let functional =
+        config.functional_property && has_type(store_owl, subj_nn, owl::FUNCTIONAL_PROPERTY)?;
+
+    ins!(store_shacl, shape, rdf::TYPE, sh::PROPERTY_SHAPE);
+    ins!(store_shacl, shape, sh::PATH, subj.clone());
+    ins_opt!(store_shacl, shape, sh::NAME, sol, label);
+    ins_opt!(store_shacl, shape, sh::DESCRIPTION, sol, description);
+    convert_cardinalities(store_shacl, shape, config, sol, subj, functional)?;
+    if config.inverse_functional_property
+        && has_type(store_owl, subj_nn, owl::INVERSE_FUNCTIONAL_PROPERTY)?
+    {
+        convert_inverse_functional(store_shacl, config, subj_nn)?;
+    }
+
+    let mut used_prop_styles = EnumMap::default();
+    for (prop, used_style) in &mut used_prop_styles {
+        *used_style = convert_property_range_or_domain(
+            store_owl,
+            store_shacl,
+            shape,
+            config,
+            sol,
+            prop,
+            is_datatype_prop,
+        )?;
+    }
+    Ok(used_prop_styles)
+}
+
+fn convert_properties(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
     log::info!("Converting properties ...");
     if let QueryResults::Solutions(solutions) = store_owl.query(Q_PROPERTIES.to_owned())? {
         let mut used_prop_styles = EnumMap::from_fn(|_| HashSet::new());
-        // let mut used_domain_prop_styles = HashSet::new();
         for sol_res in solutions {
             let sol = sol_res?;
-            let subj = sol.get("s").unwrap();
+            let subj = get_required(&sol, "s")?;
             log::info!("");
             log::info!("Property: {subj}");
 
-            type2shape!(shape, subj);
-
-            ins!(store_shacl, shape, rdf::TYPE, sh::PROPERTY_SHAPE);
-            ins!(store_shacl, shape, sh::PATH, subj.clone());
-            ins_opt!(store_shacl, shape, sh::NAME, sol, label);
-            ins_opt!(store_shacl, shape, sh::DESCRIPTION, sol, description);
-            ins_opt!(store_shacl, shape, sh::MIN_COUNT, sol, minCardinaity);
-            ins_opt!(store_shacl, shape, sh::MAX_COUNT, sol, maxCardinaity);
-            ins_opt!(store_shacl, shape, sh::MIN_COUNT, sol, cardinaity);
-            ins_opt!(store_shacl, shape, sh::MAX_COUNT, sol, cardinaity);
-
-            for (prop, used_style) in &mut used_prop_styles {
-                used_style.extend(convert_property_range_or_domain(
-                    store_shacl,
-                    shape,
-                    config,
-                    &sol,
-                    prop,
-                )?);
+            let prop_res = convert_property(store_owl, store_shacl, config, &sol, subj);
+            if let Some(used_styles) = handle_axiom_result(config, subj, prop_res)? {
+                for (prop, used_style) in used_styles {
+                    used_prop_styles[prop].extend(used_style);
+                }
             }
         }
         for (prop, used_style) in &mut used_prop_styles {
@@ -420,9 +950,9 @@ fn convert_properties(store_owl: &Store, store_shacl: &mut Store, config: &Confi
                         .join(", ")
                 );
                 match action {
-                    OdityHandling::Error => return Err(msg.into()),
+                    OdityHandling::Error => return Err(ConversionError::Oddity { iri: None, msg }),
                     OdityHandling::Warn => log::warn!("{msg}"),
-                    OdityHandling::Ignore => panic!("This should never happen"),
+                    OdityHandling::Ignore => unreachable!(),
                 }
             }
         }
@@ -462,23 +992,80 @@ fn convert_properties(store_owl: &Store, store_shacl: &mut Store, config: &Confi
     Ok(())
 }
 
-pub fn convert(store_owl: &Store, config: &Config) -> Res<()> {
-    let mut store_shacl = Store::new()?;
+/**
+ * Converts OWL ontologies into SHACL shapes.
+ *
+ * Both the source ontology and the resulting shapes
+ * live in (in-memory) stores;
+ * reading and writing them from/to files is left to the caller.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Converter {
+    config: Config,
+}
+
+impl Converter {
+    #[must_use]
+    pub const fn new(config: Config) -> Self {
+        Self { config }
+    }
 
-    convert_classes(store_owl, &mut store_shacl)?;
-    convert_properties(store_owl, &mut store_shacl, config)?;
+    #[must_use]
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
 
-    // Output
-    // let mut serializer = serializer!(prefixes);
-    // let output = serializer.serialize_graph(&mut scm)?.as_str();
-    // println!("The resulting graph\n{}", output);
+    /**
+     * Converts the OWL ontology found in the default graph of `store_owl`
+     * into SHACL shapes, which get returned in the default graph
+     * of a newly created, in-memory store.
+     *
+     * # Errors
+     *
+     * If querying the source store or writing to the result store fails,
+     * or if an oddity or unsupported construct is detected
+     * in the source ontology that is configured to be handled as an error.
+     */
+    pub fn convert(&self, store_owl: &Store) -> Res<Store> {
+        let store_shacl = Store::new()?;
 
-    let mut shacl_file = File::create("target/shacl.ttl")?;
-    store_shacl.dump_graph(
-        &mut shacl_file,
-        GraphFormat::Turtle,
-        GraphNameRef::DefaultGraph,
-    )?;
+        convert_classes(store_owl, &store_shacl, &self.config)?;
+        equivalent_class::convert_class_expressions(store_owl, &store_shacl, &self.config)?;
+        convert_properties(store_owl, &store_shacl, &self.config)?;
+        anonymous_range::convert_anonymous_ranges(store_owl, &store_shacl, &self.config)?;
+        inverse::convert_inverses(store_owl, &store_shacl, &self.config)?;
+        sub_property::convert_sub_properties(store_owl, &store_shacl, &self.config)?;
+        property_pair::convert_property_pairs(store_owl, &store_shacl, &self.config)?;
+        characteristics::convert_characteristics(store_owl, &store_shacl, &self.config)?;
+        local_property::localize_property_shapes(&store_shacl, &self.config)?;
+        restriction::convert_restrictions(store_owl, &store_shacl, &self.config)?;
+        disjoint::convert_disjoint_classes(store_owl, &store_shacl, &self.config)?;
+        class_hierarchy::convert_class_hierarchy(store_owl, &store_shacl, &self.config)?;
+        equivalent_class::merge_equivalent_classes(store_owl, &store_shacl, &self.config)?;
+        closed::close_node_shapes(&store_shacl, &self.config)?;
+        shape_names::finalize_shape_names(store_owl, &store_shacl, &self.config)?;
 
-    Ok(())
+        Ok(store_shacl)
+    }
+
+    /**
+     * Converts the OWL ontology made up of `triples`.
+     * See [`Self::convert`] for details.
+     *
+     * # Errors
+     *
+     * See [`Self::convert`].
+     */
+    pub fn convert_triples<I>(&self, triples: I) -> Res<Store>
+    where
+        I: IntoIterator<Item = Triple>,
+    {
+        let store_owl = Store::new()?;
+        store_owl.extend(
+            triples
+                .into_iter()
+                .map(|triple| triple.in_graph(GraphName::DefaultGraph)),
+        )?;
+        self.convert(&store_owl)
+    }
 }
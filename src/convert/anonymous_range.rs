@@ -0,0 +1,394 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts anonymous (blank node) ranges of properties and restrictions,
+//! which are class or datatype expressions, rather than named ones.
+//! Named ranges are handled by [`super::insert_range`],
+//! except for included ones (`*:rangeIncludes`),
+//! which are all alternatives to each other, named or not.
+
+use const_format::concatcp;
+use once_cell::sync::Lazy;
+use oxigraph::model::{BlankNode, GraphName, NamedNodeRef, Quad, SubjectRef, Term};
+use oxigraph::sparql::{Query, QueryResults};
+use oxigraph::store::Store;
+
+use super::{
+    as_named, datatype_restriction, get_required, handle_axiom_result, has_type, insert_list,
+    insert_range, objects, one_of, read_list, type2shape, Res, QUERY_PRELUDE,
+};
+use crate::config::Config;
+use crate::error::ConversionError;
+use crate::vocab::{owl, sh};
+
+const QS_ANONYMOUS_RANGES: &str = r"
+SELECT ?property ?range
+WHERE {
+    ?property rdfs:range ?range .
+    FILTER isBlank(?range) .
+}
+ORDER BY ?property
+";
+query_parser!(Q_ANONYMOUS_RANGES, QS_ANONYMOUS_RANGES);
+
+const QS_INCLUDED_RANGES: &str = r"
+SELECT ?property ?range
+WHERE {
+    ?property schema:rangeIncludes | dcam:rangeIncludes | dcid:rangeIncludes ?range .
+}
+ORDER BY ?property ?range
+";
+query_parser!(Q_INCLUDED_RANGES, QS_INCLUDED_RANGES);
+
+/**
+ * Constrains the values of `shape` to the named or anonymous class
+ * or datatype `range`.
+ *
+ * # Errors
+ *
+ * If `range` is an anonymous class expression we do not support.
+ */
+pub fn insert_range_expression(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    shape: SubjectRef,
+    range: &Term,
+    is_datatype_prop: bool,
+) -> Res<()> {
+    if let Term::NamedNode(range) = range {
+        insert_range(
+            store_owl,
+            store_shacl,
+            shape,
+            config,
+            range.as_ref(),
+            is_datatype_prop,
+        )
+    } else if insert_anonymous_range(
+        store_owl,
+        store_shacl,
+        config,
+        shape,
+        range,
+        is_datatype_prop,
+    )? {
+        Ok(())
+    } else {
+        Err(ConversionError::UnsupportedConstruct {
+            iri: range.to_string(),
+            what: "class expression".to_owned(),
+        })
+    }
+}
+
+/**
+ * Constrains the values of `shape` with `pred` (e.g. `sh:and`)
+ * to a list of shapes, one for each of `members`,
+ * or directly to the only member, if there is just one.
+ */
+pub fn insert_logical(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    shape: SubjectRef,
+    pred: NamedNodeRef,
+    members: &[Term],
+    is_datatype_prop: bool,
+) -> Res<()> {
+    if let [member] = members {
+        return insert_range_expression(
+            store_owl,
+            store_shacl,
+            config,
+            shape,
+            member,
+            is_datatype_prop,
+        );
+    }
+    let mut member_shapes = Vec::new();
+    for member in members {
+        let member_shape = BlankNode::default();
+        insert_range_expression(
+            store_owl,
+            store_shacl,
+            config,
+            member_shape.as_ref().into(),
+            member,
+            is_datatype_prop,
+        )?;
+        member_shapes.push(member_shape.into());
+    }
+    let member_shapes = insert_list(store_shacl, member_shapes)?;
+    ins!(store_shacl, shape, pred, member_shapes);
+    Ok(())
+}
+
+/**
+ * Converts `owl:intersectionOf` into `sh:and`.
+ */
+fn insert_intersection(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    shape: SubjectRef,
+    range: SubjectRef,
+    is_datatype_prop: bool,
+) -> Res<bool> {
+    let Some(members) = objects(store_owl, range, owl::INTERSECTION_OF)?
+        .into_iter()
+        .next()
+    else {
+        return Ok(false);
+    };
+    let members = read_list(store_owl, &members)?;
+    insert_logical(
+        store_owl,
+        store_shacl,
+        config,
+        shape,
+        sh::AND,
+        &members,
+        is_datatype_prop,
+    )?;
+    Ok(true)
+}
+
+/**
+ * Converts `owl:unionOf` into `sh:or`.
+ */
+fn insert_union(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    shape: SubjectRef,
+    range: SubjectRef,
+    is_datatype_prop: bool,
+) -> Res<bool> {
+    let Some(members) = objects(store_owl, range, owl::UNION_OF)?.into_iter().next() else {
+        return Ok(false);
+    };
+    let members = read_list(store_owl, &members)?;
+    insert_logical(
+        store_owl,
+        store_shacl,
+        config,
+        shape,
+        sh::OR,
+        &members,
+        is_datatype_prop,
+    )?;
+    Ok(true)
+}
+
+/**
+ * Converts `owl:complementOf` into `sh:not`.
+ */
+fn insert_complement(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    shape: SubjectRef,
+    range: SubjectRef,
+    is_datatype_prop: bool,
+) -> Res<bool> {
+    let Some(complement) = objects(store_owl, range, owl::COMPLEMENT_OF)?
+        .into_iter()
+        .next()
+    else {
+        return Ok(false);
+    };
+    let complement_shape = BlankNode::default();
+    insert_range_expression(
+        store_owl,
+        store_shacl,
+        config,
+        complement_shape.as_ref().into(),
+        &complement,
+        is_datatype_prop,
+    )?;
+    ins!(store_shacl, shape, sh::NOT, complement_shape.as_ref());
+    Ok(true)
+}
+
+/**
+ * If `range` is an anonymous class or datatype expression we support,
+ * constrains the values of `shape` accordingly, and returns `true`.
+ */
+pub fn insert_anonymous_range(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    shape: SubjectRef,
+    range: &Term,
+    is_datatype_prop: bool,
+) -> Res<bool> {
+    let Term::BlankNode(range) = range else {
+        return Ok(false);
+    };
+    let range = range.as_ref().into();
+    Ok(one_of::insert_one_of(store_owl, store_shacl, shape, range)?
+        || datatype_restriction::insert_datatype_restriction(store_owl, store_shacl, shape, range)?
+        || insert_intersection(
+            store_owl,
+            store_shacl,
+            config,
+            shape,
+            range,
+            is_datatype_prop,
+        )?
+        || insert_union(
+            store_owl,
+            store_shacl,
+            config,
+            shape,
+            range,
+            is_datatype_prop,
+        )?
+        || insert_complement(
+            store_owl,
+            store_shacl,
+            config,
+            shape,
+            range,
+            is_datatype_prop,
+        )?)
+}
+
+fn convert_anonymous_range(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    property: &Term,
+    range: &Term,
+) -> Res<()> {
+    let shape = type2shape(config, property, "property")?;
+    if has_type(store_shacl, &shape, sh::PROPERTY_SHAPE)? {
+        let is_datatype_prop = has_type(
+            store_owl,
+            as_named(property, "property")?,
+            owl::DATATYPE_PROPERTY,
+        )?;
+        insert_anonymous_range(
+            store_owl,
+            store_shacl,
+            config,
+            shape.as_ref().into(),
+            range,
+            is_datatype_prop,
+        )?;
+    }
+    Ok(())
+}
+
+/**
+ * The alternatives among the included ranges,
+ * with the members of included unions taking the place of the unions.
+ * Named ranges come first, sorted, as the store does not keep any order;
+ * the members of each union keep their order.
+ */
+fn flatten_included(store_owl: &Store, ranges: &[Term]) -> Res<Vec<Term>> {
+    let mut named = Vec::new();
+    let mut unions = Vec::new();
+    for range in ranges {
+        let union = match range {
+            Term::BlankNode(range) => objects(store_owl, range.into(), owl::UNION_OF)?
+                .into_iter()
+                .next(),
+            Term::NamedNode(_) | Term::Literal(_) | Term::Triple(_) => None,
+        };
+        match union {
+            Some(members) => unions.push(flatten_included(
+                store_owl,
+                &read_list(store_owl, &members)?,
+            )?),
+            None if matches!(range, Term::NamedNode(_)) => named.push(range.clone()),
+            None => unions.push(vec![range.clone()]),
+        }
+    }
+    named.sort_by_key(ToString::to_string);
+    // Only the named members are stable across loads
+    unions.sort_by_cached_key(|members| {
+        members
+            .iter()
+            .filter(|member| matches!(member, Term::NamedNode(_)))
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    });
+    let mut alternatives: Vec<Term> = Vec::new();
+    for member in named.into_iter().chain(unions.into_iter().flatten()) {
+        if !alternatives.contains(&member) {
+            alternatives.push(member);
+        }
+    }
+    Ok(alternatives)
+}
+
+/**
+ * Converts all the included ranges of `property` (`*:rangeIncludes`)
+ * into a single `sh:or`, as each of them is an alternative.
+ */
+fn convert_included_ranges(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    property: &Term,
+    ranges: &[Term],
+) -> Res<()> {
+    let shape = type2shape(config, property, "property")?;
+    if has_type(store_shacl, &shape, sh::PROPERTY_SHAPE)? {
+        let is_datatype_prop = has_type(
+            store_owl,
+            as_named(property, "property")?,
+            owl::DATATYPE_PROPERTY,
+        )?;
+        insert_logical(
+            store_owl,
+            store_shacl,
+            config,
+            shape.as_ref().into(),
+            sh::OR,
+            &flatten_included(store_owl, ranges)?,
+            is_datatype_prop,
+        )?;
+    }
+    Ok(())
+}
+
+pub fn convert_anonymous_ranges(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+) -> Res<()> {
+    log::info!("Converting anonymous ranges ...");
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_ANONYMOUS_RANGES.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let property = get_required(&sol, "property")?;
+            let range = get_required(&sol, "range")?;
+            let res = convert_anonymous_range(store_owl, store_shacl, config, property, range);
+            handle_axiom_result(config, property, res)?;
+        }
+    }
+    // property -> all its included ranges, in query order
+    let mut included: Vec<(Term, Vec<Term>)> = Vec::new();
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_INCLUDED_RANGES.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let property = get_required(&sol, "property")?;
+            let range = get_required(&sol, "range")?.clone();
+            match included.last_mut() {
+                Some((last, ranges)) if last == property => ranges.push(range),
+                _ => included.push((property.clone(), vec![range])),
+            }
+        }
+    }
+    for (property, ranges) in &included {
+        let res = convert_included_ranges(store_owl, store_shacl, config, property, ranges);
+        handle_axiom_result(config, property, res)?;
+    }
+    log::info!("Converting anonymous ranges - done.");
+
+    Ok(())
+}
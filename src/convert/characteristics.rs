@@ -0,0 +1,179 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts the property characteristics
+//! `owl:SymmetricProperty`, `owl:AsymmetricProperty`,
+//! `owl:IrreflexiveProperty` and `owl:TransitiveProperty`
+//! into SPARQL-based constraints.
+//!
+//! NOTE: These are not part of SHACL Core,
+//! and thus require a validator that supports SHACL-SPARQL.
+
+use std::collections::BTreeMap;
+
+use const_format::concatcp;
+use once_cell::sync::Lazy;
+use oxigraph::model::vocab::{rdf, rdfs};
+use oxigraph::model::{BlankNode, GraphName, Literal, NamedNode, NamedNodeRef, Quad, Term};
+use oxigraph::sparql::{Query, QueryResults};
+use oxigraph::store::Store;
+
+use super::{as_named, get_required, handle_axiom_result, shape_iri, Res, QUERY_PRELUDE};
+use crate::config::Config;
+use crate::naming::ShapeKind;
+use crate::vocab::{owl, sh};
+
+const QS_CHARACTERISTICS: &str = r"
+SELECT ?property ?characteristic
+WHERE {
+    VALUES ?characteristic {
+        owl:SymmetricProperty owl:AsymmetricProperty
+        owl:IrreflexiveProperty owl:TransitiveProperty
+    } .
+    ?property rdf:type ?characteristic .
+}
+ORDER BY ?property ?characteristic
+";
+query_parser!(Q_CHARACTERISTICS, QS_CHARACTERISTICS);
+
+/// Marks the generated shapes, as not every validator is able to use them.
+const SPARQL_NOTE: &str = "Requires a validator that supports SHACL-SPARQL.";
+
+/// Placeholder for the property in the queries of [`CHARACTERISTICS`].
+const PROPERTY_PLACEHOLDER: &str = "{property}";
+
+/**
+ * For each supported characteristic,
+ * the message and the SPARQL query of the constraint;
+ * the query selects the focus nodes (`$this`) violating it,
+ * together with the offending `?value`.
+ */
+const CHARACTERISTICS: [(NamedNodeRef<'static>, &str, &str); 4] = [
+    (
+        owl::SYMMETRIC_PROPERTY,
+        "Symmetric property {property}: {?value} lacks the inverse link to {$this}",
+        r"SELECT $this ?value
+WHERE {
+    $this {property} ?value .
+    FILTER NOT EXISTS { ?value {property} $this . }
+}",
+    ),
+    (
+        owl::ASYMMETRIC_PROPERTY,
+        "Asymmetric property {property}: {?value} also links back to {$this}",
+        r"SELECT $this ?value
+WHERE {
+    $this {property} ?value .
+    ?value {property} $this .
+}",
+    ),
+    (
+        owl::IRREFLEXIVE_PROPERTY,
+        "Irreflexive property {property}: {$this} links to itself",
+        r"SELECT $this ?value
+WHERE {
+    $this {property} $this .
+    BIND ( $this AS ?value )
+}",
+    ),
+    (
+        owl::TRANSITIVE_PROPERTY,
+        "Transitive property {property}: {$this} lacks the (transitively implied) link to {?value}",
+        r"SELECT $this ?value
+WHERE {
+    $this {property} ?intermediate .
+    ?intermediate {property} ?value .
+    FILTER NOT EXISTS { $this {property} ?value . }
+}",
+    ),
+];
+
+/**
+ * Creates a node shape `<property>SparqlShape`,
+ * targeting all subjects of `property`,
+ * with a SPARQL-based constraint for each of its `characteristics`.
+ */
+fn convert_characteristics_of(
+    store_shacl: &Store,
+    config: &Config,
+    property: &NamedNode,
+    characteristics: &[Term],
+) -> Res<()> {
+    let shape = shape_iri(config, property.as_ref(), ShapeKind::Sparql)?;
+    let shape = shape.as_ref();
+    let property_sparql = format!("<{}>", property.as_str());
+    let property_message = property.as_str();
+
+    ins!(store_shacl, shape, rdf::TYPE, sh::NODE_SHAPE);
+    ins!(
+        store_shacl,
+        shape,
+        sh::TARGET_SUBJECTS_OF,
+        property.as_ref()
+    );
+    ins!(
+        store_shacl,
+        shape,
+        rdfs::COMMENT,
+        Literal::from(SPARQL_NOTE)
+    );
+    for (characteristic, message, select) in CHARACTERISTICS {
+        if !characteristics.contains(&characteristic.into_owned().into()) {
+            continue;
+        }
+        let constraint = BlankNode::default();
+        ins!(store_shacl, shape, sh::SPARQL, constraint.as_ref());
+        ins!(
+            store_shacl,
+            constraint.as_ref(),
+            rdf::TYPE,
+            sh::SPARQL_CONSTRAINT
+        );
+        ins!(
+            store_shacl,
+            constraint.as_ref(),
+            sh::MESSAGE,
+            Literal::from(message.replace(PROPERTY_PLACEHOLDER, property_message))
+        );
+        ins!(
+            store_shacl,
+            constraint.as_ref(),
+            sh::SELECT,
+            Literal::from(select.replace(PROPERTY_PLACEHOLDER, &property_sparql))
+        );
+    }
+
+    Ok(())
+}
+
+pub fn convert_characteristics(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
+    if !config.sparql_constraints {
+        return Ok(());
+    }
+    log::info!("Converting property characteristics into SHACL-SPARQL constraints ...");
+    let mut characteristics: BTreeMap<NamedNode, Vec<Term>> = BTreeMap::new();
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_CHARACTERISTICS.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let property = get_required(&sol, "property")?;
+            let characteristic = get_required(&sol, "characteristic")?;
+            let res = as_named(property, "property");
+            if let Some(property) = handle_axiom_result(config, property, res)? {
+                characteristics
+                    .entry(property.into_owned())
+                    .or_default()
+                    .push(characteristic.clone());
+            }
+        }
+    }
+    for (property, property_characteristics) in &characteristics {
+        log::info!("Characteristics of {property}");
+        let res =
+            convert_characteristics_of(store_shacl, config, property, property_characteristics);
+        handle_axiom_result(config, &property.clone().into(), res)?;
+    }
+    log::info!("Converting property characteristics into SHACL-SPARQL constraints - done.");
+
+    Ok(())
+}
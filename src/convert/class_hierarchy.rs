@@ -0,0 +1,137 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Relates the node shapes of sub-classes to those of their super-classes
+//! (`rdfs:subClassOf`), either by referencing them with `sh:node`,
+//! or by copying their property shapes.
+//! This has to run after all the property shapes of the classes are known.
+
+use const_format::concatcp;
+use once_cell::sync::Lazy;
+use oxigraph::model::{GraphName, GraphNameRef, Literal, NamedNode, Quad, Term};
+use oxigraph::sparql::{Query, QueryResults};
+use oxigraph::store::Store;
+
+use super::{get_required, handle_axiom_result, has_type, type2shape, Res, QUERY_PRELUDE};
+use crate::config::{ClassHierarchy, Config};
+use crate::vocab::sh;
+
+const QS_SUPER_CLASSES: &str = r"
+SELECT ?sub ?super ?direct ?cyclic
+WHERE {
+    # The named classes in an intersection a class is equivalent to
+    # (or a sub-class of) are super-classes as well
+    ?sub (rdfs:subClassOf|(rdfs:subClassOf|owl:equivalentClass)/owl:intersectionOf/rdf:rest*/rdf:first)+ ?super .
+    FILTER ( isIRI(?sub) && isIRI(?super) && ?sub != ?super )
+    BIND ( EXISTS { ?sub (rdfs:subClassOf|(rdfs:subClassOf|owl:equivalentClass)/owl:intersectionOf/rdf:rest*/rdf:first) ?super } AS ?direct )
+    BIND ( EXISTS { ?super (rdfs:subClassOf|(rdfs:subClassOf|owl:equivalentClass)/owl:intersectionOf/rdf:rest*/rdf:first)+ ?sub } AS ?cyclic )
+}
+ORDER BY ?sub ?super
+";
+query_parser!(Q_SUPER_CLASSES, QS_SUPER_CLASSES);
+
+fn is_true(value: Option<&Term>) -> bool {
+    matches!(value, Some(Term::Literal(lit)) if lit == &Literal::from(true))
+}
+
+/**
+ * Returns the node shape generated for the class `class`,
+ * if there is one.
+ */
+fn node_shape(store_shacl: &Store, config: &Config, class: &Term) -> Res<Option<NamedNode>> {
+    let shape = type2shape(config, class, "class")?;
+    Ok(has_type(store_shacl, &shape, sh::NODE_SHAPE)?.then_some(shape))
+}
+
+/**
+ * Copies all the property shapes of `super_shape` into `sub_shape`.
+ */
+fn copy_property_shapes(
+    store_shacl: &Store,
+    sub_shape: &NamedNode,
+    super_shape: &NamedNode,
+) -> Res<()> {
+    let property_shapes = store_shacl
+        .quads_for_pattern(
+            Some(super_shape.as_ref().into()),
+            Some(sh::PROPERTY),
+            None,
+            Some(GraphNameRef::DefaultGraph),
+        )
+        .map(|quad| Ok(quad?.object))
+        .collect::<Res<Vec<_>>>()?;
+    for property_shape in property_shapes {
+        ins!(
+            store_shacl,
+            sub_shape.as_ref(),
+            sh::PROPERTY,
+            property_shape
+        );
+    }
+    Ok(())
+}
+
+fn convert_super_class(
+    store_shacl: &Store,
+    config: &Config,
+    sub: &Term,
+    super_class: &Term,
+    direct: bool,
+    cyclic: bool,
+) -> Res<()> {
+    let (Some(sub_shape), Some(super_shape)) = (
+        node_shape(store_shacl, config, sub)?,
+        node_shape(store_shacl, config, super_class)?,
+    ) else {
+        return Ok(());
+    };
+    match config.class_hierarchy {
+        ClassHierarchy::None => {}
+        // Indirect super-classes are reached through the direct ones
+        ClassHierarchy::Node if !direct => {}
+        ClassHierarchy::Node if cyclic => {
+            // Recursive shapes are not well-defined in SHACL
+            log::warn!(
+                "Not linking {sub_shape} to {super_shape} with sh:node, \
+                because {sub} and {super_class} are sub-classes of each other"
+            );
+        }
+        ClassHierarchy::Node => {
+            ins!(
+                store_shacl,
+                sub_shape.as_ref(),
+                sh::NODE,
+                super_shape.as_ref()
+            );
+        }
+        ClassHierarchy::Flatten => copy_property_shapes(store_shacl, &sub_shape, &super_shape)?,
+    }
+    Ok(())
+}
+
+pub fn convert_class_hierarchy(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
+    if config.class_hierarchy == ClassHierarchy::None {
+        return Ok(());
+    }
+    log::info!("Converting class hierarchy ...");
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_SUPER_CLASSES.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let sub = get_required(&sol, "sub")?;
+            let super_class = get_required(&sol, "super")?;
+            let res = convert_super_class(
+                store_shacl,
+                config,
+                sub,
+                super_class,
+                is_true(sol.get("direct")),
+                is_true(sol.get("cyclic")),
+            );
+            handle_axiom_result(config, sub, res)?;
+        }
+    }
+    log::info!("Converting class hierarchy - done.");
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Closes the generated node shapes (`sh:closed true`),
+//! which has to be done after all other passes,
+//! as only then all the property shapes of a class are known.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{GraphName, GraphNameRef, NamedNode, NamedNodeRef, Quad, Subject, Term};
+use oxigraph::store::Store;
+
+use super::{as_subject, has_type, insert_list, objects, read_list, Res};
+use crate::config::Config;
+use crate::vocab::{basics, sh};
+
+fn subjects(store: &Store, pred: NamedNodeRef, obj: &Term) -> Res<Vec<Subject>> {
+    store
+        .quads_for_pattern(
+            None,
+            Some(pred),
+            Some(obj.as_ref()),
+            Some(GraphNameRef::DefaultGraph),
+        )
+        .map(|quad| Ok(quad?.subject))
+        .collect()
+}
+
+/**
+ * The properties a node shape constrains with its property shapes:
+ * the ones that are allowed anyway by `sh:closed`,
+ * as they are the `sh:path` of one of them,
+ * and the ones that are only reachable through an `sh:alternativePath`.
+ */
+#[derive(Debug, Default)]
+struct Paths {
+    direct: BTreeSet<NamedNode>,
+    alternative: BTreeSet<NamedNode>,
+}
+
+impl Paths {
+    fn all(&self) -> impl Iterator<Item = &NamedNode> {
+        self.direct.iter().chain(&self.alternative)
+    }
+}
+
+fn paths(store_shacl: &Store, node_shape: &NamedNode) -> Res<Paths> {
+    let mut paths = Paths::default();
+    for property_shape in objects(store_shacl, node_shape.into(), sh::PROPERTY)? {
+        let Some(property_shape) = as_subject(&property_shape) else {
+            continue;
+        };
+        for path in objects(store_shacl, property_shape, sh::PATH)? {
+            match &path {
+                Term::NamedNode(path) => {
+                    paths.direct.insert(path.clone());
+                }
+                Term::BlankNode(path) => {
+                    for alternatives in objects(store_shacl, path.into(), sh::ALTERNATIVE_PATH)? {
+                        for alternative in read_list(store_shacl, &alternatives)? {
+                            if let Term::NamedNode(alternative) = alternative {
+                                paths.alternative.insert(alternative);
+                            }
+                        }
+                    }
+                }
+                Term::Literal(_) | Term::Triple(_) => {}
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/**
+ * Collects all the node shapes reachable from `start`
+ * through `links` (`sh:node` links in one of the two directions).
+ */
+fn node_linked(
+    links: &BTreeMap<NamedNode, BTreeSet<NamedNode>>,
+    start: &NamedNode,
+    reachable: &mut BTreeSet<NamedNode>,
+) {
+    if let Some(linked) = links.get(start) {
+        for shape in linked {
+            if reachable.insert(shape.clone()) {
+                node_linked(links, shape, reachable);
+            }
+        }
+    }
+}
+
+/**
+ * Marks all node shapes of classes as closed, ignoring `rdf:type`,
+ * the configured [`Config::ignored_properties`],
+ * and all the properties that are constrained by shapes
+ * linked to it with `sh:node` (in either direction);
+ * the latter either supply constraints of super-classes,
+ * or are themselves applied to instances of sub-classes.
+ */
+pub fn close_node_shapes(store_shacl: &Store, config: &Config) -> Res<()> {
+    if !config.closed {
+        return Ok(());
+    }
+    log::info!("Closing node shapes ...");
+    let node_shape_type: Term = sh::NODE_SHAPE.into_owned().into();
+    let mut node_shapes = BTreeMap::new();
+    for shape in subjects(store_shacl, rdf::TYPE, &node_shape_type)? {
+        if let Subject::NamedNode(shape) = shape {
+            if objects(store_shacl, shape.as_ref().into(), sh::TARGET_CLASS)?.is_empty() {
+                // e.g. the shapes of SPARQL constraints on properties
+                continue;
+            }
+            let shape_paths = paths(store_shacl, &shape)?;
+            node_shapes.insert(shape, shape_paths);
+        }
+    }
+
+    let mut links_up: BTreeMap<NamedNode, BTreeSet<NamedNode>> = BTreeMap::new();
+    let mut links_down: BTreeMap<NamedNode, BTreeSet<NamedNode>> = BTreeMap::new();
+    for shape in node_shapes.keys() {
+        for linked in objects(store_shacl, shape.into(), sh::NODE)? {
+            if let Term::NamedNode(linked) = linked {
+                if has_type(store_shacl, &linked, sh::NODE_SHAPE)? {
+                    links_up
+                        .entry(shape.clone())
+                        .or_default()
+                        .insert(linked.clone());
+                    links_down.entry(linked).or_default().insert(shape.clone());
+                }
+            }
+        }
+    }
+
+    for (shape, shape_paths) in &node_shapes {
+        let mut related = BTreeSet::new();
+        node_linked(&links_up, shape, &mut related);
+        node_linked(&links_down, shape, &mut related);
+
+        let mut ignored: BTreeSet<NamedNode> = config.ignored_properties.iter().cloned().collect();
+        ignored.insert(rdf::TYPE.into_owned());
+        ignored.extend(shape_paths.alternative.iter().cloned());
+        for related_shape in &related {
+            if let Some(related_paths) = node_shapes.get(related_shape) {
+                ignored.extend(related_paths.all().cloned());
+            }
+        }
+        let ignored = ignored
+            .into_iter()
+            .filter(|property| !shape_paths.direct.contains(property))
+            .map(Term::from)
+            .collect::<Vec<_>>();
+
+        ins!(store_shacl, shape.as_ref(), sh::CLOSED, *basics::BOOL_TRUE);
+        let ignored = insert_list(store_shacl, ignored)?;
+        ins!(store_shacl, shape.as_ref(), sh::IGNORED_PROPERTIES, ignored);
+    }
+    log::info!("Closing node shapes - done.");
+
+    Ok(())
+}
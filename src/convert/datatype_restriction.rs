@@ -0,0 +1,172 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts OWL 2 datatype restrictions, as in
+//! `[ a rdfs:Datatype ; owl:onDatatype xsd:integer ; owl:withRestrictions ( [ xsd:minInclusive 0 ] ) ]`,
+//! into `sh:datatype` plus the SHACL value constraints
+//! corresponding to the facets.
+
+use oxigraph::model::{GraphName, GraphNameRef, Literal, NamedNodeRef, Quad, SubjectRef, Term};
+use oxigraph::store::Store;
+
+use super::{as_subject, objects, read_list, Res};
+use crate::error::ConversionError;
+use crate::vocab::{owl, sh, xsd};
+
+/**
+ * Splits leading inline flags (e.g. `(?i)`) off an XSD pattern,
+ * as SHACL has a separate `sh:flags` for them.
+ */
+fn split_flags(pattern: &str) -> (&str, Option<&str>) {
+    pattern
+        .strip_prefix("(?")
+        .and_then(|rest| rest.split_once(')'))
+        .filter(|(flags, _)| !flags.is_empty() && flags.chars().all(|flag| "smixq".contains(flag)))
+        .map_or((pattern, None), |(flags, pattern)| (pattern, Some(flags)))
+}
+
+/**
+ * Converts a single facet-value pair into SHACL constraints.
+ */
+fn facet_constraints(
+    datatype: SubjectRef,
+    facet: NamedNodeRef,
+    value: &Term,
+) -> Res<Vec<(NamedNodeRef<'static>, Term)>> {
+    let length = || -> Res<Term> {
+        let Term::Literal(value) = value else {
+            return Err(ConversionError::UnsupportedConstruct {
+                iri: datatype.to_string(),
+                what: format!("facet {facet} with the non-literal value {value}"),
+            });
+        };
+        let length: u64 =
+            value
+                .value()
+                .parse()
+                .map_err(|_err| ConversionError::UnsupportedConstruct {
+                    iri: datatype.to_string(),
+                    what: format!(
+                    "facet {facet} with the value '{value}', which is not a non-negative integer"
+                ),
+                })?;
+        Ok(Literal::from(length).into())
+    };
+    Ok(if facet == xsd::MIN_INCLUSIVE {
+        vec![(sh::MIN_INCLUSIVE, value.clone())]
+    } else if facet == xsd::MAX_INCLUSIVE {
+        vec![(sh::MAX_INCLUSIVE, value.clone())]
+    } else if facet == xsd::MIN_EXCLUSIVE {
+        vec![(sh::MIN_EXCLUSIVE, value.clone())]
+    } else if facet == xsd::MAX_EXCLUSIVE {
+        vec![(sh::MAX_EXCLUSIVE, value.clone())]
+    } else if facet == xsd::LENGTH {
+        let length = length()?;
+        vec![(sh::MIN_LENGTH, length.clone()), (sh::MAX_LENGTH, length)]
+    } else if facet == xsd::MIN_LENGTH {
+        vec![(sh::MIN_LENGTH, length()?)]
+    } else if facet == xsd::MAX_LENGTH {
+        vec![(sh::MAX_LENGTH, length()?)]
+    } else if facet == xsd::PATTERN {
+        let Term::Literal(value) = value else {
+            return Err(ConversionError::UnsupportedConstruct {
+                iri: datatype.to_string(),
+                what: format!("pattern facet with the non-literal value {value}"),
+            });
+        };
+        let (pattern, flags) = split_flags(value.value());
+        // XSD patterns always match the whole value, SPARQL ones anywhere
+        let mut constraints = vec![(sh::PATTERN, Literal::from(format!("^({pattern})$")).into())];
+        if let Some(flags) = flags {
+            constraints.push((sh::FLAGS, Literal::from(flags).into()));
+        }
+        constraints
+    } else {
+        return Err(ConversionError::UnsupportedConstruct {
+            iri: datatype.to_string(),
+            what: format!("datatype restriction with the facet {facet}"),
+        });
+    })
+}
+
+/**
+ * If `datatype` is a datatype restriction,
+ * constrains the values of `shape` to its base datatype and facets,
+ * and returns `true`.
+ */
+pub fn insert_datatype_restriction(
+    store_owl: &Store,
+    store_shacl: &Store,
+    shape: SubjectRef,
+    datatype: SubjectRef,
+) -> Res<bool> {
+    let Some(base) = objects(store_owl, datatype, owl::ON_DATATYPE)?
+        .into_iter()
+        .next()
+    else {
+        return Ok(false);
+    };
+    let Term::NamedNode(base) = base else {
+        return Err(ConversionError::UnsupportedConstruct {
+            iri: datatype.to_string(),
+            what: format!("datatype restriction on the datatype expression {base}"),
+        });
+    };
+
+    // Collect everything first, so we insert nothing on errors
+    let mut constraints = vec![(sh::DATA_TYPE, base.into())];
+    for facets in objects(store_owl, datatype, owl::WITH_RESTRICTIONS)? {
+        for facet_value in read_list(store_owl, &facets)? {
+            let Some(facet_value) = as_subject(&facet_value) else {
+                continue;
+            };
+            for quad in store_owl.quads_for_pattern(
+                Some(facet_value),
+                None,
+                None,
+                Some(GraphNameRef::DefaultGraph),
+            ) {
+                let quad = quad?;
+                constraints.extend(facet_constraints(
+                    datatype,
+                    quad.predicate.as_ref(),
+                    &quad.object,
+                )?);
+            }
+        }
+    }
+    for (pred, value) in constraints {
+        ins!(store_shacl, shape, pred, value);
+    }
+    Ok(true)
+}
+
+/**
+ * Like [`insert_datatype_restriction`], but for a named datatype,
+ * which is defined through a datatype restriction,
+ * either directly, or with `owl:equivalentClass` as OWL 2 does it.
+ */
+pub fn insert_named_datatype_restriction(
+    store_owl: &Store,
+    store_shacl: &Store,
+    shape: SubjectRef,
+    datatype: NamedNodeRef,
+) -> Res<bool> {
+    if insert_datatype_restriction(store_owl, store_shacl, shape, datatype.into())? {
+        return Ok(true);
+    }
+    for equivalent in objects(store_owl, datatype.into(), owl::EQUIVALENT_CLASS)? {
+        if let Term::BlankNode(equivalent) = equivalent {
+            if insert_datatype_restriction(
+                store_owl,
+                store_shacl,
+                shape,
+                equivalent.as_ref().into(),
+            )? {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
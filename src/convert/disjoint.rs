@@ -0,0 +1,169 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts disjoint classes (`owl:disjointWith`, `owl:AllDisjointClasses`),
+//! so that data typed as instance of more than one of them is reported.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use const_format::concatcp;
+use once_cell::sync::Lazy;
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{BlankNode, GraphName, NamedNode, Quad, Term};
+use oxigraph::sparql::{Query, QueryResults};
+use oxigraph::store::Store;
+
+use super::{
+    as_named, get_required, handle_axiom_result, has_type, insert_list, read_list, type2shape, Res,
+    QUERY_PRELUDE,
+};
+use crate::config::{Config, DisjointClasses};
+use crate::vocab::sh;
+
+const QS_DISJOINT_PAIRS: &str = r"
+SELECT ?class ?other
+WHERE {
+    ?class owl:disjointWith ?other .
+    FILTER ( ?class != ?other )
+}
+ORDER BY ?class ?other
+";
+query_parser!(Q_DISJOINT_PAIRS, QS_DISJOINT_PAIRS);
+
+const QS_ALL_DISJOINT_CLASSES: &str = r"
+SELECT ?group ?members
+WHERE {
+    ?group
+        rdf:type owl:AllDisjointClasses ;
+        owl:members ?members .
+}
+";
+query_parser!(Q_ALL_DISJOINT_CLASSES, QS_ALL_DISJOINT_CLASSES);
+
+/**
+ * A set of classes that are pairwise disjoint,
+ * sorted, so equal groups are only converted once.
+ */
+type Group = BTreeSet<NamedNode>;
+
+fn as_group<'a>(classes: impl IntoIterator<Item = &'a Term>) -> Res<Group> {
+    classes
+        .into_iter()
+        .map(|class| Ok(as_named(class, "disjoint class")?.into_owned()))
+        .collect()
+}
+
+fn disjoint_groups(store_owl: &Store, config: &Config) -> Res<BTreeSet<Group>> {
+    let mut groups = BTreeSet::new();
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_DISJOINT_PAIRS.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let class = get_required(&sol, "class")?;
+            let res = get_required(&sol, "other").and_then(|other| as_group([class, other]));
+            if let Some(group) = handle_axiom_result(config, class, res)? {
+                groups.insert(group);
+            }
+        }
+    }
+    if let QueryResults::Solutions(solutions) =
+        store_owl.query(Q_ALL_DISJOINT_CLASSES.to_owned())?
+    {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let group = get_required(&sol, "group")?;
+            let res = get_required(&sol, "members")
+                .and_then(|members| read_list(store_owl, members))
+                .and_then(|members| as_group(&members));
+            if let Some(group) = handle_axiom_result(config, group, res)? {
+                if group.len() > 1 {
+                    groups.insert(group);
+                }
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/**
+ * Returns the node shape generated for `class`, if there is one.
+ */
+fn node_shape(store_shacl: &Store, config: &Config, class: &NamedNode) -> Res<Option<NamedNode>> {
+    let shape = type2shape(config, &class.clone().into(), "disjoint class")?;
+    Ok(has_type(store_shacl, &shape, sh::NODE_SHAPE)?.then_some(shape))
+}
+
+/**
+ * Adds `sh:not [ sh:class <other> ]` to the node shape of each class,
+ * for each of the classes it is disjoint with.
+ */
+fn convert_per_class(store_shacl: &Store, config: &Config, groups: &BTreeSet<Group>) -> Res<()> {
+    let mut disjoint: BTreeMap<&NamedNode, BTreeSet<&NamedNode>> = BTreeMap::new();
+    for group in groups {
+        for class in group {
+            disjoint
+                .entry(class)
+                .or_default()
+                .extend(group.iter().filter(|other| *other != class));
+        }
+    }
+    for (class, others) in disjoint {
+        let Some(shape) = node_shape(store_shacl, config, class)? else {
+            log::warn!("No node shape for disjoint class {class}");
+            continue;
+        };
+        for other in others {
+            let not_shape = BlankNode::default();
+            ins!(store_shacl, shape.as_ref(), sh::NOT, not_shape.as_ref());
+            ins!(store_shacl, not_shape.as_ref(), sh::CLASS, other.as_ref());
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Generates a single node shape per group,
+ * targeting all its classes, and requiring each instance
+ * to be an instance of exactly one of them.
+ */
+fn convert_per_group(store_shacl: &Store, groups: &BTreeSet<Group>) -> Res<()> {
+    for group in groups {
+        let shape = BlankNode::default();
+        let mut members = Vec::new();
+        ins!(store_shacl, shape.as_ref(), rdf::TYPE, sh::NODE_SHAPE);
+        for class in group {
+            ins!(
+                store_shacl,
+                shape.as_ref(),
+                sh::TARGET_CLASS,
+                class.as_ref()
+            );
+            let member = BlankNode::default();
+            ins!(store_shacl, member.as_ref(), sh::CLASS, class.as_ref());
+            members.push(member.into());
+        }
+        let members = insert_list(store_shacl, members)?;
+        ins!(store_shacl, shape.as_ref(), sh::XONE, members);
+    }
+    Ok(())
+}
+
+pub fn convert_disjoint_classes(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+) -> Res<()> {
+    if config.disjoint_classes == DisjointClasses::None {
+        return Ok(());
+    }
+    log::info!("Converting disjoint classes ...");
+    let groups = disjoint_groups(store_owl, config)?;
+    match config.disjoint_classes {
+        DisjointClasses::None => {}
+        DisjointClasses::Class => convert_per_class(store_shacl, config, &groups)?,
+        DisjointClasses::Group => convert_per_group(store_shacl, &groups)?,
+    }
+    log::info!("Converting disjoint classes - done.");
+
+    Ok(())
+}
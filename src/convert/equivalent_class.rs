@@ -0,0 +1,223 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts `owl:equivalentClass`:
+//! Named equivalent classes (`ex:Human owl:equivalentClass ex:Person`)
+//! share a single node shape, targeting all of them,
+//! and the constraints of class expressions a class is equivalent to
+//! get folded into the node shape of the class.
+//!
+//! Restrictions and named classes within such class expressions
+//! (including those within an `owl:intersectionOf`)
+//! are handled like the ones given with `rdfs:subClassOf`,
+//! see [`super::restriction`] and [`super::class_hierarchy`].
+
+use std::collections::BTreeSet;
+
+use const_format::concatcp;
+use once_cell::sync::Lazy;
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{
+    GraphName, GraphNameRef, NamedNode, NamedNodeRef, Quad, QuadRef, SubjectRef, Term,
+};
+use oxigraph::sparql::{Query, QueryResults};
+use oxigraph::store::Store;
+
+use super::anonymous_range::insert_anonymous_range;
+use super::{
+    get_required, handle_axiom_result, has_type, objects, read_list, rename_node, type2shape, Res,
+    QUERY_PRELUDE,
+};
+use crate::config::Config;
+use crate::error::ConversionError;
+use crate::vocab::{owl, sh};
+
+const QS_EQUIVALENT_CLASSES: &str = r"
+SELECT DISTINCT ?class ?equivalent
+WHERE {
+    {
+        ?class owl:equivalentClass ?equivalent .
+    } UNION {
+        ?equivalent owl:equivalentClass ?class .
+    }
+    FILTER ( isIRI(?class) && ?class != ?equivalent )
+}
+ORDER BY ?class ?equivalent
+";
+query_parser!(Q_EQUIVALENT_CLASSES, QS_EQUIVALENT_CLASSES);
+
+/**
+ * Returns the node shape generated for the class `class`,
+ * if there is one.
+ */
+fn node_shape(store_shacl: &Store, config: &Config, class: &Term) -> Res<Option<NamedNode>> {
+    let shape = type2shape(config, class, "class")?;
+    Ok(has_type(store_shacl, &shape, sh::NODE_SHAPE)?.then_some(shape))
+}
+
+/**
+ * Whether `expression` is an `owl:Restriction`,
+ * which gets handled in [`super::restriction`].
+ */
+fn is_restriction(store_owl: &Store, expression: SubjectRef) -> Res<bool> {
+    Ok(objects(store_owl, expression, rdf::TYPE)?.contains(&owl::RESTRICTION.into_owned().into()))
+}
+
+/**
+ * Folds the constraints of the anonymous class `expression`
+ * into the node shape `shape`.
+ */
+fn fold_expression(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    shape: &NamedNode,
+    expression: &Term,
+) -> Res<()> {
+    let Term::BlankNode(expression_bn) = expression else {
+        return Ok(());
+    };
+    if is_restriction(store_owl, expression_bn.into())? {
+        return Ok(());
+    }
+    let intersection = objects(store_owl, expression_bn.into(), owl::INTERSECTION_OF)?;
+    if !intersection.is_empty() {
+        for list in &intersection {
+            for member in read_list(store_owl, list)? {
+                // named members are handled like super-classes
+                fold_expression(store_owl, store_shacl, config, shape, &member)?;
+            }
+        }
+        return Ok(());
+    }
+    if insert_anonymous_range(
+        store_owl,
+        store_shacl,
+        config,
+        shape.as_ref().into(),
+        expression,
+        false,
+    )? {
+        Ok(())
+    } else {
+        Err(ConversionError::UnsupportedConstruct {
+            iri: shape.to_string(),
+            what: format!("equivalence to the class expression {expression}"),
+        })
+    }
+}
+
+/**
+ * Folds the class expressions the classes are equivalent to
+ * into their node shapes.
+ * This is part of the conversion of the classes themselves,
+ * and has to run before the node shapes get shared, see [`merge_equivalent_classes`].
+ */
+pub fn convert_class_expressions(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+) -> Res<()> {
+    log::info!("Converting equivalent class expressions ...");
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_EQUIVALENT_CLASSES.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let class = get_required(&sol, "class")?;
+            let equivalent = get_required(&sol, "equivalent")?;
+            if !matches!(equivalent, Term::BlankNode(_)) {
+                continue;
+            }
+            let res = node_shape(store_shacl, config, class).and_then(|shape| match shape {
+                Some(shape) => fold_expression(store_owl, store_shacl, config, &shape, equivalent),
+                None => Ok(()),
+            });
+            handle_axiom_result(config, class, res)?;
+        }
+    }
+    log::info!("Converting equivalent class expressions - done.");
+
+    Ok(())
+}
+
+/**
+ * Collects the groups of (transitively) equivalent named classes.
+ */
+fn equivalence_groups(store_owl: &Store) -> Res<Vec<BTreeSet<NamedNode>>> {
+    let mut groups: Vec<BTreeSet<NamedNode>> = Vec::new();
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_EQUIVALENT_CLASSES.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let (Term::NamedNode(class), Term::NamedNode(equivalent)) = (
+                get_required(&sol, "class")?,
+                get_required(&sol, "equivalent")?,
+            ) else {
+                continue;
+            };
+            let mut merged: BTreeSet<NamedNode> = [class.clone(), equivalent.clone()].into();
+            groups.retain(|group| {
+                if group.contains(class) || group.contains(equivalent) {
+                    merged.extend(group.iter().cloned());
+                    false
+                } else {
+                    true
+                }
+            });
+            groups.push(merged);
+        }
+    }
+    groups.sort();
+    Ok(groups)
+}
+
+/**
+ * Moves all the triples about and pointing to `from` over to `to`.
+ */
+fn merge_shape(store_shacl: &Store, from: NamedNodeRef, to: NamedNodeRef) -> Res<()> {
+    rename_node(store_shacl, from, to.into())?;
+    // Links between the equivalent classes' shapes are now self-references
+    store_shacl.remove(QuadRef::new(to, sh::NODE, to, GraphNameRef::DefaultGraph))?;
+    Ok(())
+}
+
+/**
+ * Makes the equivalent named classes share a single node shape,
+ * the one of the first class of each group (by IRI) that has a shape,
+ * which then targets all of them.
+ * This has to run after all the other passes added to the node shapes.
+ */
+pub fn merge_equivalent_classes(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+) -> Res<()> {
+    log::info!("Merging the shapes of equivalent classes ...");
+    for group in equivalence_groups(store_owl)? {
+        let mut shapes = Vec::new();
+        for class in &group {
+            let class_term = class.clone().into();
+            let res = node_shape(store_shacl, config, &class_term);
+            if let Some(Some(shape)) = handle_axiom_result(config, &class_term, res)? {
+                shapes.push(shape);
+            }
+        }
+        let Some((shared, others)) = shapes.split_first() else {
+            continue;
+        };
+        log::info!("Equivalent classes {group:?} share the shape {shared}");
+        for other in others {
+            merge_shape(store_shacl, other.as_ref(), shared.as_ref())?;
+        }
+        for class in &group {
+            ins!(
+                store_shacl,
+                shared.as_ref(),
+                sh::TARGET_CLASS,
+                class.as_ref()
+            );
+        }
+    }
+    log::info!("Merging the shapes of equivalent classes - done.");
+
+    Ok(())
+}
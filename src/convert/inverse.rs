@@ -0,0 +1,356 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts `owl:inverseOf` pairs, as in `ex:hasPart owl:inverseOf ex:partOf`:
+//! the domains of one property are the ranges of the other, and vice versa,
+//! and the range classes may optionally constrain their subjects
+//! through `sh:path [ sh:inversePath <property> ]`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use const_format::concatcp;
+use once_cell::sync::Lazy;
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{BlankNode, GraphName, NamedNode, Quad, SubjectRef, Term};
+use oxigraph::sparql::{Query, QueryResults};
+use oxigraph::store::Store;
+
+use super::{
+    as_named, domains, get_required, handle_axiom_result, has_type, insert_list, objects,
+    own_specs, property_shape, ranges, read_list, type2shape, Res, QUERY_PRELUDE,
+};
+use crate::config::{Config, Inheritance, RDProperty};
+use crate::vocab::sh;
+
+const QS_INVERSES: &str = r"
+SELECT DISTINCT ?property ?inverse
+WHERE {
+    {
+        ?property owl:inverseOf ?inverse .
+    } UNION {
+        ?inverse owl:inverseOf ?property .
+    }
+    FILTER ( ?property != ?inverse )
+}
+ORDER BY ?property ?inverse
+";
+query_parser!(Q_INVERSES, QS_INVERSES);
+
+/// The domain classes of properties; those that are alternatives to each other
+/// come with the union or the property of their `*:domainIncludes` as `?group`.
+const QS_DOMAINS: &str = r"
+SELECT ?property ?group ?class
+WHERE {
+    {
+        ?property rdfs:domain ?class .
+    } UNION {
+        ?property rdfs:domain ?group .
+        ?group owl:unionOf/rdf:rest*/rdf:first ?class .
+    } UNION {
+        ?property schema:domainIncludes | dcam:domainIncludes | dcid:domainIncludes ?class .
+        BIND ( ?property AS ?group )
+    }
+    FILTER ( isIRI(?property) && isIRI(?class) )
+}
+ORDER BY ?property ?class
+";
+query_parser!(Q_DOMAINS, QS_DOMAINS);
+
+/**
+ * The domain classes of a property in the ontology:
+ * multiple `rdfs:domain`s all apply,
+ * while the members of a union or of `*:domainIncludes` are alternatives.
+ */
+#[derive(Debug, Default)]
+struct Domains {
+    required: Vec<Term>,
+    alternatives: Vec<(Term, Vec<Term>)>,
+}
+
+fn ontology_domains(store_owl: &Store, config: &Config) -> Res<HashMap<NamedNode, Domains>> {
+    let mut domains: HashMap<NamedNode, Domains> = HashMap::new();
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_DOMAINS.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let property = get_required(&sol, "property")?;
+            let class = get_required(&sol, "class")?.clone();
+            let res = as_named(property, "property");
+            let Some(property) = handle_axiom_result(config, property, res)? else {
+                continue;
+            };
+            let prop_domains = domains.entry(property.into_owned()).or_default();
+            let Some(group) = sol.get("group") else {
+                prop_domains.required.push(class);
+                continue;
+            };
+            let alternatives = &mut prop_domains.alternatives;
+            if let Some((_, classes)) = alternatives.iter_mut().find(|(known, _)| known == group) {
+                classes.push(class);
+            } else {
+                alternatives.push((group.clone(), vec![class]));
+            }
+        }
+    }
+    Ok(domains)
+}
+
+/**
+ * Sorts `terms` and removes duplicates,
+ * so the generated shapes do not depend on the order in the store.
+ */
+fn sort_unique(terms: &mut Vec<Term>) {
+    terms.sort_by_key(ToString::to_string);
+    terms.dedup();
+}
+
+/**
+ * The classes targeted by the node shapes
+ * that have `shape` as one of their property shapes.
+ */
+fn domain_classes(store_shacl: &Store, shape: &NamedNode) -> Res<Vec<Term>> {
+    let mut classes = Vec::new();
+    for domain in domains(store_shacl, shape)? {
+        if let Term::NamedNode(domain) = domain {
+            classes.extend(objects(
+                store_shacl,
+                domain.as_ref().into(),
+                sh::TARGET_CLASS,
+            )?);
+        }
+    }
+    sort_unique(&mut classes);
+    Ok(classes)
+}
+
+/**
+ * The classes the values of `shape` have to be instances of;
+ * for `sh:or` ranges, these are the classes of all the alternatives.
+ */
+fn range_classes(store_shacl: &Store, shape: &NamedNode) -> Res<Vec<Term>> {
+    let mut classes = Vec::new();
+    for (pred, range) in ranges(store_shacl, shape)? {
+        if pred == sh::CLASS {
+            classes.push(range);
+        } else if pred == sh::OR {
+            for member in read_list(store_shacl, &range)? {
+                if let Term::BlankNode(member) = member {
+                    classes.extend(objects(store_shacl, member.as_ref().into(), sh::CLASS)?);
+                }
+            }
+        } else {
+            // Other kinds of ranges (e.g. datatypes) can not be domains
+        }
+    }
+    sort_unique(&mut classes);
+    Ok(classes)
+}
+
+/**
+ * Constrains the values of `shape` to be instances of the domain `classes`:
+ * one `sh:class` for each of them,
+ * except for alternatives in the ontology `domains`, which get an `sh:or` per group.
+ */
+fn insert_classes(
+    store_shacl: &Store,
+    shape: SubjectRef,
+    classes: &[Term],
+    domains: Option<&Domains>,
+) -> Res<()> {
+    let (required, alternatives) = domains.map_or((&[][..], &[][..]), |domains| {
+        (domains.required.as_slice(), domains.alternatives.as_slice())
+    });
+    for class in classes {
+        let is_alternative = alternatives
+            .iter()
+            .any(|(_, members)| members.contains(class));
+        if required.contains(class) || !is_alternative {
+            ins!(store_shacl, shape, sh::CLASS, class.clone());
+        }
+    }
+    for (_, members) in alternatives {
+        let mut members: Vec<Term> = members
+            .iter()
+            .filter(|member| classes.contains(member))
+            .cloned()
+            .collect();
+        sort_unique(&mut members);
+        if let [class] = members.as_slice() {
+            ins!(store_shacl, shape, sh::CLASS, class.clone());
+        } else if !members.is_empty() {
+            let mut shapes = Vec::new();
+            for class in members {
+                let member = BlankNode::default();
+                ins!(store_shacl, member.as_ref(), sh::CLASS, class);
+                shapes.push(member.into());
+            }
+            let list = insert_list(store_shacl, shapes)?;
+            ins!(store_shacl, shape, sh::OR, list);
+        } else {
+            // None of the alternatives made it into the shapes
+        }
+    }
+    Ok(())
+}
+
+/**
+ * The node shapes generated for `classes`, as far as there are any.
+ */
+fn node_shapes(store_shacl: &Store, config: &Config, classes: &[Term]) -> Res<Vec<NamedNode>> {
+    let mut shapes = Vec::new();
+    for class in classes {
+        if !matches!(class, Term::NamedNode(_)) {
+            continue;
+        }
+        let shape = type2shape(config, class, "range class")?;
+        if has_type(store_shacl, &shape, sh::NODE_SHAPE)? {
+            shapes.push(shape);
+        }
+    }
+    Ok(shapes)
+}
+
+fn inherit(
+    store_shacl: &Store,
+    config: &Config,
+    prop: RDProperty,
+    shape: &NamedNode,
+    inverse_shape: &NamedNode,
+    inverse_domains: Option<&Domains>,
+) -> Res<()> {
+    match prop {
+        RDProperty::Range => {
+            let classes = domain_classes(store_shacl, inverse_shape)?;
+            insert_classes(
+                store_shacl,
+                shape.as_ref().into(),
+                &classes,
+                inverse_domains,
+            )?;
+        }
+        RDProperty::Domain => {
+            let classes = range_classes(store_shacl, inverse_shape)?;
+            for domain in node_shapes(store_shacl, config, &classes)? {
+                ins!(store_shacl, domain.as_ref(), sh::PROPERTY, shape.as_ref());
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Adds a property shape with `sh:path [ sh:inversePath <prop> ]`
+ * to the node shapes of the range classes of `prop`,
+ * requiring the subjects pointing to them to be of the domain classes of `prop`.
+ */
+fn insert_inverse_path(
+    store_shacl: &Store,
+    config: &Config,
+    prop: &NamedNode,
+    shape: &NamedNode,
+    domains: Option<&Domains>,
+) -> Res<()> {
+    let classes = domain_classes(store_shacl, shape)?;
+    if classes.is_empty() {
+        return Ok(());
+    }
+    for range_shape in node_shapes(store_shacl, config, &range_classes(store_shacl, shape)?)? {
+        let path = BlankNode::default();
+        ins!(store_shacl, path.as_ref(), sh::INVERSE_PATH, prop.as_ref());
+        let inverse_shape = BlankNode::default();
+        ins!(
+            store_shacl,
+            range_shape.as_ref(),
+            sh::PROPERTY,
+            inverse_shape.as_ref()
+        );
+        ins!(
+            store_shacl,
+            inverse_shape.as_ref(),
+            rdf::TYPE,
+            sh::PROPERTY_SHAPE
+        );
+        ins!(store_shacl, inverse_shape.as_ref(), sh::PATH, path.as_ref());
+        insert_classes(
+            store_shacl,
+            inverse_shape.as_ref().into(),
+            &classes,
+            domains,
+        )?;
+    }
+    Ok(())
+}
+
+pub fn convert_inverses(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
+    log::info!("Converting inverse properties ...");
+    // (property, inverse) - both directions of each pair
+    let mut pairs: Vec<(NamedNode, NamedNode)> = Vec::new();
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_INVERSES.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let property = get_required(&sol, "property")?;
+            let res = as_named(property, "property").and_then(|prop| {
+                Ok((
+                    prop,
+                    as_named(get_required(&sol, "inverse")?, "inverse property")?,
+                ))
+            });
+            if let Some((prop, inverse)) = handle_axiom_result(config, property, res)? {
+                pairs.push((prop.into_owned(), inverse.into_owned()));
+            }
+        }
+    }
+
+    let ontology_domains = ontology_domains(store_owl, config)?;
+
+    // Only the properties own domains and ranges count
+    // when deciding whether to inherit, so we collect them up-front.
+    let mut own_specs_cache = HashMap::new();
+    for (prop, _) in &pairs {
+        if let Some(shape) = property_shape(store_shacl, config, prop)? {
+            let specs = own_specs(store_shacl, &shape)?;
+            own_specs_cache.insert(shape, specs);
+        }
+    }
+
+    for (prop, inverse) in &pairs {
+        let (Some(shape), Some(inverse_shape)) = (
+            property_shape(store_shacl, config, prop)?,
+            property_shape(store_shacl, config, inverse)?,
+        ) else {
+            continue;
+        };
+        log::info!("Inverse of {prop}: {inverse}");
+        let own = own_specs_cache.get(&shape).copied().unwrap_or_default();
+        for (rd_prop, inheritance) in config.inverse_inheritance {
+            let do_inherit = match inheritance {
+                Inheritance::None => false,
+                Inheritance::IfMissing => !own[rd_prop],
+                Inheritance::Always => true,
+            };
+            if do_inherit {
+                inherit(
+                    store_shacl,
+                    config,
+                    rd_prop,
+                    &shape,
+                    &inverse_shape,
+                    ontology_domains.get(inverse),
+                )?;
+            }
+        }
+    }
+
+    if config.inverse_path {
+        let props: BTreeSet<&NamedNode> = pairs.iter().map(|(prop, _)| prop).collect();
+        for prop in props {
+            if let Some(shape) = property_shape(store_shacl, config, prop)? {
+                let domains = ontology_domains.get(prop);
+                insert_inverse_path(store_shacl, config, prop, &shape, domains)?;
+            }
+        }
+    }
+    log::info!("Converting inverse properties - done.");
+
+    Ok(())
+}
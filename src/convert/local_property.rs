@@ -0,0 +1,184 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Mints a property shape per class and property,
+//! see [`Config::property_shapes`].
+//! The global property shape of each property gets copied
+//! into every node shape it is attached to,
+//! after which the constraints specific to a single class
+//! (e.g. those of restrictions) can be added to the copy of that class alone.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{
+    BlankNode, GraphName, GraphNameRef, NamedNode, NamedNodeRef, Quad, QuadRef, Subject,
+    SubjectRef, Term,
+};
+use oxigraph::store::Store;
+
+use super::{as_subject, copy_term, new_iri, objects, objects_of, path_property, Res};
+use crate::config::{Config, PropertyShapes};
+use crate::naming::split_iri;
+use crate::vocab::sh;
+
+/**
+ * Mints an IRI for the property shape of `property` local to `node_shape`,
+ * e.g. `ex:PersonShape-name`, which is not yet used for anything else.
+ */
+fn mint_iri(store_shacl: &Store, node_shape: &NamedNode, property: &NamedNode) -> Res<NamedNode> {
+    let (_, local) = split_iri(property.as_str());
+    let mut iri = new_iri(format!("{}-{local}", node_shape.as_str()))?;
+    let mut counter = 1;
+    while !objects(store_shacl, iri.as_ref().into(), rdf::TYPE)?.is_empty() {
+        // Properties with the same local name from different namespaces
+        counter += 1;
+        iri = new_iri(format!("{}-{local}-{counter}", node_shape.as_str()))?;
+    }
+    Ok(iri)
+}
+
+/**
+ * Creates a new, empty property shape for `property`,
+ * local to `node_shape`, named as configured.
+ */
+fn new_local_shape(
+    store_shacl: &Store,
+    config: &Config,
+    node_shape: &NamedNode,
+    property: &NamedNode,
+) -> Res<Subject> {
+    let shape: Subject = match config.property_shapes {
+        PropertyShapes::Global | PropertyShapes::Blank => BlankNode::default().into(),
+        PropertyShapes::Named => mint_iri(store_shacl, node_shape, property)?.into(),
+    };
+    ins!(
+        store_shacl,
+        node_shape.as_ref(),
+        sh::PROPERTY,
+        shape.as_ref()
+    );
+    ins!(store_shacl, shape.as_ref(), rdf::TYPE, sh::PROPERTY_SHAPE);
+    Ok(shape)
+}
+
+/**
+ * Returns the property shape of `property` local to `node_shape`,
+ * creating it if it does not yet exist,
+ * or `None` if property shapes are global.
+ */
+pub fn local_property_shape(
+    store_shacl: &Store,
+    config: &Config,
+    node_shape: &NamedNode,
+    property: NamedNodeRef,
+) -> Res<Option<Subject>> {
+    if config.property_shapes == PropertyShapes::Global {
+        return Ok(None);
+    }
+    for shape in objects(store_shacl, node_shape.as_ref().into(), sh::PROPERTY)? {
+        let Some(shape_subj) = as_subject(&shape) else {
+            continue;
+        };
+        let paths = objects(store_shacl, shape_subj, sh::PATH)?;
+        let qualified = objects(store_shacl, shape_subj, sh::QUALIFIED_VALUE_SHAPE)?;
+        if paths == [property.into_owned().into()] && qualified.is_empty() {
+            return Ok(Some(shape_subj.into_owned()));
+        }
+    }
+    let shape = new_local_shape(store_shacl, config, node_shape, &property.into_owned())?;
+    ins!(store_shacl, shape.as_ref(), sh::PATH, property);
+    Ok(Some(shape))
+}
+
+/**
+ * Removes all the triples about `subj`, and - recursively -
+ * about the blank nodes only it refers to.
+ */
+fn remove_tree(store_shacl: &Store, subj: SubjectRef) -> Res<()> {
+    for (predicate, object) in objects_of(store_shacl, subj)? {
+        store_shacl.remove(QuadRef::new(
+            subj,
+            &predicate,
+            &object,
+            GraphNameRef::DefaultGraph,
+        ))?;
+        if let Term::BlankNode(object_bn) = &object {
+            let referenced = store_shacl
+                .quads_for_pattern(None, None, Some(object.as_ref()), None)
+                .next()
+                .transpose()?
+                .is_some();
+            if !referenced {
+                remove_tree(store_shacl, object_bn.into())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Collects the named property shapes,
+ * together with the node shapes they are attached to.
+ */
+fn attached_property_shapes(store_shacl: &Store) -> Res<BTreeMap<NamedNode, BTreeSet<NamedNode>>> {
+    let mut attached: BTreeMap<NamedNode, BTreeSet<NamedNode>> = BTreeMap::new();
+    for quad in store_shacl.quads_for_pattern(
+        None,
+        Some(sh::PROPERTY),
+        None,
+        Some(GraphNameRef::DefaultGraph),
+    ) {
+        let quad = quad?;
+        if let (Subject::NamedNode(node_shape), Term::NamedNode(shape)) =
+            (quad.subject, quad.object)
+        {
+            attached.entry(shape).or_default().insert(node_shape);
+        }
+    }
+    Ok(attached)
+}
+
+/**
+ * Replaces the global property shapes attached to node shapes
+ * with copies local to each of these node shapes.
+ * Global property shapes of properties without a domain are kept as they are.
+ * This has to run after all the passes using the global property shapes
+ * (see [`super::property_shape`]), and before the restrictions are converted,
+ * so those end up in the local shapes.
+ */
+pub fn localize_property_shapes(store_shacl: &Store, config: &Config) -> Res<()> {
+    if config.property_shapes == PropertyShapes::Global {
+        return Ok(());
+    }
+    log::info!("Localizing property shapes ...");
+    for (shape, node_shapes) in attached_property_shapes(store_shacl)? {
+        let mut property = None;
+        for path in objects(store_shacl, shape.as_ref().into(), sh::PATH)? {
+            property = property.or(path_property(store_shacl, &path)?);
+        }
+        let Some(property) = property else {
+            log::warn!("Can not localize the property shape {shape}, as it has no usable path");
+            continue;
+        };
+        let constraints = objects_of(store_shacl, shape.as_ref().into())?;
+        for node_shape in node_shapes {
+            let local = new_local_shape(store_shacl, config, &node_shape, &property)?;
+            for (predicate, object) in &constraints {
+                let object = copy_term(store_shacl, object)?;
+                ins!(store_shacl, local.as_ref(), predicate.as_ref(), object);
+            }
+            store_shacl.remove(QuadRef::new(
+                &node_shape,
+                sh::PROPERTY,
+                &shape,
+                GraphNameRef::DefaultGraph,
+            ))?;
+        }
+        remove_tree(store_shacl, shape.as_ref().into())?;
+    }
+    log::info!("Localizing property shapes - done.");
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts enumerations (`owl:oneOf`) of individuals or data values,
+//! be they named classes/datatypes or anonymous ones,
+//! into `sh:in` lists.
+
+use oxigraph::model::{GraphName, Quad, SubjectRef};
+use oxigraph::store::Store;
+
+use super::{insert_list, objects, read_list, Res};
+use crate::vocab::{owl, sh};
+
+/**
+ * If `range` is an enumeration, constrains the values of `shape`
+ * to its members with `sh:in`, and returns `true`.
+ */
+pub fn insert_one_of(
+    store_owl: &Store,
+    store_shacl: &Store,
+    shape: SubjectRef,
+    range: SubjectRef,
+) -> Res<bool> {
+    let Some(members) = objects(store_owl, range, owl::ONE_OF)?.into_iter().next() else {
+        return Ok(false);
+    };
+    let members = read_list(store_owl, &members)?;
+    let members = insert_list(store_shacl, members)?;
+    ins!(store_shacl, shape, sh::IN, members);
+    Ok(true)
+}
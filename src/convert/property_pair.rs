@@ -0,0 +1,128 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts the relations between pairs of properties
+//! `owl:equivalentProperty` and `owl:propertyDisjointWith`
+//! into the property pair constraints `sh:equals` and `sh:disjoint`,
+//! on the node shapes of the domains of the two properties.
+
+use const_format::concatcp;
+use once_cell::sync::Lazy;
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{BlankNode, GraphName, NamedNode, NamedNodeRef, Quad, Term};
+use oxigraph::sparql::{Query, QueryResults};
+use oxigraph::store::Store;
+
+use super::{
+    as_named, domains, get_required, handle_axiom_result, property_shape, Res, QUERY_PRELUDE,
+};
+use crate::config::Config;
+use crate::error::ConversionError;
+use crate::vocab::{owl, sh};
+
+const QS_PROPERTY_PAIRS: &str = r"
+SELECT DISTINCT ?property ?relation ?other
+WHERE {
+    VALUES ?relation { owl:equivalentProperty owl:propertyDisjointWith } .
+    {
+        ?property ?relation ?other .
+    } UNION {
+        ?other ?relation ?property .
+    }
+    # Both relations are symmetric, so we only need each pair once
+    FILTER ( STR(?property) < STR(?other) )
+}
+ORDER BY ?property ?relation ?other
+";
+query_parser!(Q_PROPERTY_PAIRS, QS_PROPERTY_PAIRS);
+
+/**
+ * The SHACL property pair constraint corresponding to `relation`.
+ */
+fn pair_constraint(relation: &Term) -> Res<NamedNodeRef<'static>> {
+    let relation_nn = as_named(relation, "property relation")?;
+    if relation_nn == owl::EQUIVALENT_PROPERTY {
+        Ok(sh::EQUALS)
+    } else if relation_nn == owl::PROPERTY_DISJOINT_WITH {
+        Ok(sh::DISJOINT)
+    } else {
+        Err(ConversionError::UnexpectedBinding {
+            iri: relation.to_string(),
+            var: "relation".to_owned(),
+            value: relation.to_string(),
+            expected: "a property relation",
+        })
+    }
+}
+
+/**
+ * The node shapes of the domains of `property`.
+ */
+fn domain_shapes(store_shacl: &Store, config: &Config, property: &NamedNode) -> Res<Vec<Term>> {
+    match property_shape(store_shacl, config, property)? {
+        Some(shape) => domains(store_shacl, &shape),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn convert_property_pair(
+    store_shacl: &Store,
+    config: &Config,
+    property: &NamedNode,
+    constraint: NamedNodeRef,
+    other: &NamedNode,
+) -> Res<()> {
+    let mut node_shapes = domain_shapes(store_shacl, config, property)?;
+    node_shapes.extend(domain_shapes(store_shacl, config, other)?);
+    node_shapes.sort_by_key(ToString::to_string);
+    node_shapes.dedup();
+    if node_shapes.is_empty() {
+        log::warn!(
+            "Neither {property} nor {other} has a domain, \
+            so there is no node shape to put their {constraint} constraint on"
+        );
+    }
+    for node_shape in node_shapes {
+        let Term::NamedNode(node_shape) = node_shape else {
+            continue;
+        };
+        let shape = BlankNode::default();
+        ins!(
+            store_shacl,
+            node_shape.as_ref(),
+            sh::PROPERTY,
+            shape.as_ref()
+        );
+        ins!(store_shacl, shape.as_ref(), rdf::TYPE, sh::PROPERTY_SHAPE);
+        ins!(store_shacl, shape.as_ref(), sh::PATH, property.as_ref());
+        ins!(store_shacl, shape.as_ref(), constraint, other.as_ref());
+    }
+    Ok(())
+}
+
+pub fn convert_property_pairs(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
+    log::info!("Converting equivalent and disjoint properties ...");
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_PROPERTY_PAIRS.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let property = get_required(&sol, "property")?;
+            let res = as_named(property, "property").and_then(|prop| {
+                let constraint = pair_constraint(get_required(&sol, "relation")?)?;
+                let other = as_named(get_required(&sol, "other")?, "related property")?;
+                log::info!("{prop} {constraint} {other}");
+                convert_property_pair(
+                    store_shacl,
+                    config,
+                    &prop.into_owned(),
+                    constraint,
+                    &other.into_owned(),
+                )
+            });
+            handle_axiom_result(config, property, res)?;
+        }
+    }
+    log::info!("Converting equivalent and disjoint properties - done.");
+
+    Ok(())
+}
@@ -0,0 +1,402 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts `owl:Restriction`s that are super-classes of named classes,
+//! as in `ex:Person rdfs:subClassOf [ a owl:Restriction ; owl:onProperty ex:name ; ... ]`,
+//! into property shapes local to the node shape of the restricted class.
+
+use std::collections::HashMap;
+
+use const_format::concatcp;
+use once_cell::sync::Lazy;
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{BlankNode, GraphName, Literal, NamedNode, NamedNodeRef, Quad, Term};
+use oxigraph::sparql::{Query, QueryResults, QuerySolution};
+use oxigraph::store::Store;
+
+use super::anonymous_range::insert_anonymous_range;
+use super::local_property::local_property_shape;
+use super::{
+    combine_cardinalities, get_required, handle_axiom_result, has_type, insert_cardinality,
+    insert_range, parse_cardinality, type2shape, Res, QUERY_PRELUDE,
+};
+use crate::config::Config;
+use crate::error::ConversionError;
+use crate::vocab::{owl, sh};
+
+const QS_RESTRICTIONS: &str = r"
+SELECT
+    ?class ?property
+    ?allValuesFrom ?someValuesFrom ?hasValue
+    ?cardinality ?minCardinality ?maxCardinality
+    ?qualifiedCardinality ?minQualifiedCardinality ?maxQualifiedCardinality
+    ?onClass ?onDataRange
+WHERE {
+    # Restrictions may also be part of an intersection,
+    # and those of equivalent classes constrain the class just the same
+    ?class (rdfs:subClassOf|owl:equivalentClass)/(owl:intersectionOf/rdf:rest*/rdf:first)? ?restriction .
+    ?restriction owl:onProperty ?property .
+    OPTIONAL {
+        ?restriction owl:allValuesFrom ?allValuesFrom .
+    } .
+    OPTIONAL {
+        ?restriction owl:someValuesFrom ?someValuesFrom .
+    } .
+    OPTIONAL {
+        ?restriction owl:hasValue ?hasValue .
+    } .
+    OPTIONAL {
+        ?restriction owl:cardinality ?cardinality .
+    } .
+    OPTIONAL {
+        ?restriction owl:minCardinality ?minCardinality .
+    } .
+    OPTIONAL {
+        ?restriction owl:maxCardinality ?maxCardinality .
+    } .
+    OPTIONAL {
+        ?restriction owl:qualifiedCardinality ?qualifiedCardinality .
+    } .
+    OPTIONAL {
+        ?restriction owl:minQualifiedCardinality ?minQualifiedCardinality .
+    } .
+    OPTIONAL {
+        ?restriction owl:maxQualifiedCardinality ?maxQualifiedCardinality .
+    } .
+    OPTIONAL {
+        ?restriction owl:onClass ?onClass .
+    } .
+    OPTIONAL {
+        ?restriction owl:onDataRange ?onDataRange .
+    } .
+}
+ORDER BY ?class ?property
+";
+query_parser!(Q_RESTRICTIONS, QS_RESTRICTIONS);
+
+/**
+ * Exact, minimum and maximum cardinalities,
+ * as given in (possibly multiple) restrictions.
+ */
+#[derive(Debug, Default)]
+struct Cardinalities {
+    exact: Vec<u64>,
+    min: Vec<u64>,
+    max: Vec<u64>,
+}
+
+impl Cardinalities {
+    fn add(&mut self, class: &Term, sol: &QuerySolution, vars: [&str; 3]) -> Res<()> {
+        for (var, values) in vars
+            .into_iter()
+            .zip([&mut self.exact, &mut self.min, &mut self.max])
+        {
+            if let Some(Term::Literal(lit)) = sol.get(var) {
+                values.push(parse_cardinality(class, lit.value())?);
+            }
+        }
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.min.is_empty() && self.max.is_empty()
+    }
+}
+
+/**
+ * The restrictions on how many of the values
+ * have to be of a certain class or data range.
+ * `owl:someValuesFrom` ends up in here as well,
+ * as it is the same as an `owl:minQualifiedCardinality` of one.
+ */
+#[derive(Debug, Default)]
+struct Qualified {
+    on_data_range: bool,
+    cardinalities: Cardinalities,
+}
+
+/**
+ * All the restrictions on one property of one class.
+ */
+#[derive(Debug, Default)]
+struct Restrictions {
+    all_values_from: Vec<Term>,
+    has_value: Vec<Term>,
+    cardinalities: Cardinalities,
+    /// In the order the values were first seen, to keep the output stable
+    qualified: Vec<(Term, Qualified)>,
+}
+
+fn push_unique(values: &mut Vec<Term>, sol: &QuerySolution, var: &str) {
+    if let Some(value) = sol.get(var) {
+        if !values.contains(value) {
+            values.push(value.clone());
+        }
+    }
+}
+
+impl Restrictions {
+    fn qualified_on(&mut self, value: &Term, on_data_range: bool) -> &mut Cardinalities {
+        let idx = self.qualified.iter().position(|(known, _)| known == value);
+        let idx = idx.unwrap_or_else(|| {
+            self.qualified.push((value.clone(), Qualified::default()));
+            self.qualified.len() - 1
+        });
+        let Some((_, qualified)) = self.qualified.get_mut(idx) else {
+            unreachable!("the index always points into the list")
+        };
+        qualified.on_data_range |= on_data_range;
+        &mut qualified.cardinalities
+    }
+
+    fn add(&mut self, class: &Term, sol: &QuerySolution) -> Res<()> {
+        push_unique(&mut self.all_values_from, sol, "allValuesFrom");
+        push_unique(&mut self.has_value, sol, "hasValue");
+        self.cardinalities.add(
+            class,
+            sol,
+            ["cardinality", "minCardinality", "maxCardinality"],
+        )?;
+        if let Some(value) = sol.get("someValuesFrom") {
+            self.qualified_on(value, false).min.push(1);
+        }
+        let qualified_vars = [
+            "qualifiedCardinality",
+            "minQualifiedCardinality",
+            "maxQualifiedCardinality",
+        ];
+        let cardinalities = match (sol.get("onClass"), sol.get("onDataRange")) {
+            (Some(value), _) => self.qualified_on(value, false),
+            (None, Some(value)) => self.qualified_on(value, true),
+            // Not valid OWL 2, but the closest meaning is
+            // to qualify on `owl:Thing`, which is the same as no qualification
+            (None, None) => &mut self.cardinalities,
+        };
+        cardinalities.add(class, sol, qualified_vars)
+    }
+
+    /**
+     * Whether there are restrictions that go into the (main) property shape;
+     * qualified ones each get a property shape of their own.
+     */
+    fn needs_property_shape(&self) -> bool {
+        !(self.all_values_from.is_empty()
+            && self.has_value.is_empty()
+            && self.cardinalities.is_empty())
+    }
+}
+
+/**
+ * Ensures that the value of a restriction is a named class or datatype;
+ * class expressions are not supported there.
+ */
+fn named_value<'a>(class: &Term, value: &'a Term, restriction: &str) -> Res<NamedNodeRef<'a>> {
+    if let Term::NamedNode(value) = value {
+        Ok(value.as_ref())
+    } else {
+        Err(ConversionError::UnsupportedConstruct {
+            iri: class.to_string(),
+            what: format!("{restriction} with the class expression {value}"),
+        })
+    }
+}
+
+/**
+ * Creates a new, blank property shape for `property`,
+ * and attaches it to `class_shape`.
+ */
+fn new_property_shape(
+    store_shacl: &Store,
+    class_shape: &NamedNode,
+    property: NamedNodeRef,
+) -> Res<BlankNode> {
+    let shape = BlankNode::default();
+    ins!(
+        store_shacl,
+        class_shape.as_ref(),
+        sh::PROPERTY,
+        shape.as_ref()
+    );
+    ins!(store_shacl, shape.as_ref(), rdf::TYPE, sh::PROPERTY_SHAPE);
+    ins!(store_shacl, shape.as_ref(), sh::PATH, property);
+    Ok(shape)
+}
+
+#[allow(clippy::too_many_lines)]
+fn convert_restrictions_on(
+    store_owl: &Store,
+    store_shacl: &Store,
+    config: &Config,
+    class: &Term,
+    property: &Term,
+    restrictions: &Restrictions,
+) -> Res<()> {
+    let class_shape = type2shape(config, class, "restricted class")?;
+    let Term::NamedNode(property) = property else {
+        return Err(ConversionError::UnsupportedConstruct {
+            iri: class.to_string(),
+            what: format!("restriction on the property expression {property}"),
+        });
+    };
+    let property = property.as_ref();
+    let is_datatype_prop = has_type(store_owl, property, owl::DATATYPE_PROPERTY)?;
+
+    if restrictions.needs_property_shape() {
+        let shape = match local_property_shape(store_shacl, config, &class_shape, property)? {
+            Some(shape) => shape,
+            None => new_property_shape(store_shacl, &class_shape, property)?.into(),
+        };
+        for value in &restrictions.all_values_from {
+            if insert_anonymous_range(
+                store_owl,
+                store_shacl,
+                config,
+                shape.as_ref(),
+                value,
+                is_datatype_prop,
+            )? {
+                continue;
+            }
+            let range = named_value(class, value, "owl:allValuesFrom")?;
+            insert_range(
+                store_owl,
+                store_shacl,
+                shape.as_ref(),
+                config,
+                range,
+                is_datatype_prop,
+            )?;
+        }
+        for value in &restrictions.has_value {
+            ins!(store_shacl, shape.as_ref(), sh::HAS_VALUE, value.clone());
+        }
+        let subj_desc = format!("Restriction of class {class} on property {property}");
+        let cards = &restrictions.cardinalities;
+        let cardinality = combine_cardinalities(
+            config,
+            class,
+            &subj_desc,
+            &cards.exact,
+            &cards.min,
+            &cards.max,
+        )?;
+        insert_cardinality(store_shacl, shape.as_ref(), cardinality)?;
+    }
+
+    // As a property shape may only have a single `sh:qualifiedValueShape`,
+    // each of these needs a property shape of its own.
+    for (value, qualified) in &restrictions.qualified {
+        let subj_desc =
+            format!("Qualified restriction of class {class} on property {property} to {value}");
+        let cards = &qualified.cardinalities;
+        let cardinality = combine_cardinalities(
+            config,
+            class,
+            &subj_desc,
+            &cards.exact,
+            &cards.min,
+            &cards.max,
+        )?;
+        let min = cardinality.min.filter(|min| *min > 0);
+        if min.is_none() && cardinality.max.is_none() {
+            // Does not constrain anything
+            continue;
+        }
+
+        let value_shape = BlankNode::default();
+        let is_datatype = is_datatype_prop || qualified.on_data_range;
+        if !insert_anonymous_range(
+            store_owl,
+            store_shacl,
+            config,
+            value_shape.as_ref().into(),
+            value,
+            is_datatype,
+        )? {
+            let range = named_value(class, value, "qualified restriction")?;
+            insert_range(
+                store_owl,
+                store_shacl,
+                value_shape.as_ref().into(),
+                config,
+                range,
+                is_datatype,
+            )?;
+        }
+        let shape = new_property_shape(store_shacl, &class_shape, property)?;
+        ins!(
+            store_shacl,
+            shape.as_ref(),
+            sh::QUALIFIED_VALUE_SHAPE,
+            value_shape.as_ref()
+        );
+        if let Some(min) = min {
+            ins!(
+                store_shacl,
+                shape.as_ref(),
+                sh::QUALIFIED_MIN_COUNT,
+                Literal::from(min)
+            );
+        }
+        if let Some(max) = cardinality.max {
+            ins!(
+                store_shacl,
+                shape.as_ref(),
+                sh::QUALIFIED_MAX_COUNT,
+                Literal::from(max)
+            );
+        }
+        if config.qualified_value_shapes_disjoint {
+            ins!(
+                store_shacl,
+                shape.as_ref(),
+                sh::QUALIFIED_VALUE_SHAPES_DISJOINT,
+                Literal::from(true)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn convert_restrictions(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
+    log::info!("Converting restrictions ...");
+    // Kept in query order (`ORDER BY ?class ?property`), so the output is stable;
+    // the map only indexes into the list.
+    let mut restrictions: Vec<(Term, Term, Restrictions)> = Vec::new();
+    let mut indices: HashMap<(Term, Term), usize> = HashMap::new();
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_RESTRICTIONS.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let class = get_required(&sol, "class")?;
+            let property = get_required(&sol, "property")?;
+            let idx = *indices
+                .entry((class.clone(), property.clone()))
+                .or_insert_with(|| {
+                    restrictions.push((class.clone(), property.clone(), Restrictions::default()));
+                    restrictions.len() - 1
+                });
+            let Some((_, _, class_prop_restrictions)) = restrictions.get_mut(idx) else {
+                unreachable!("the index always points into the list")
+            };
+            let res = class_prop_restrictions.add(class, &sol);
+            handle_axiom_result(config, class, res)?;
+        }
+    }
+    for (class, property, class_prop_restrictions) in &restrictions {
+        log::info!("Restrictions of {class} on {property}");
+        let res = convert_restrictions_on(
+            store_owl,
+            store_shacl,
+            config,
+            class,
+            property,
+            class_prop_restrictions,
+        );
+        handle_axiom_result(config, class, res)?;
+    }
+    log::info!("Converting restrictions - done.");
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Finalizes the names of the generated shapes:
+//! checks them for collisions with the IRIs used in the source ontology
+//! and with each other,
+//! or turns them into blank nodes, see [`Config::shape_naming`].
+//! This has to run after all the other passes,
+//! as those find the shapes by their (minted) IRIs.
+
+use std::collections::BTreeSet;
+
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{BlankNode, GraphNameRef, NamedNode, Subject, Term};
+use oxigraph::store::Store;
+
+use super::{objects, path_property, rename_node, shape_iri, Res};
+use crate::config::{Config, OdityHandling};
+use crate::error::ConversionError;
+use crate::naming::{ShapeKind, ShapeNaming};
+use crate::vocab::sh;
+
+/**
+ * All the named node and property shapes, sorted.
+ */
+fn named_shapes(store_shacl: &Store) -> Res<BTreeSet<NamedNode>> {
+    let mut shapes = BTreeSet::new();
+    for shape_type in [sh::NODE_SHAPE, sh::PROPERTY_SHAPE] {
+        for quad in store_shacl.quads_for_pattern(
+            None,
+            Some(rdf::TYPE),
+            Some(shape_type.into()),
+            Some(GraphNameRef::DefaultGraph),
+        ) {
+            if let Subject::NamedNode(shape) = quad?.subject {
+                shapes.insert(shape);
+            }
+        }
+    }
+    Ok(shapes)
+}
+
+/**
+ * Whether `iri` is used anywhere in `store`.
+ */
+fn is_used(store: &Store, iri: &NamedNode) -> Res<bool> {
+    let graph = Some(GraphNameRef::DefaultGraph);
+    for quads in [
+        store.quads_for_pattern(Some(iri.into()), None, None, graph),
+        store.quads_for_pattern(None, Some(iri.into()), None, graph),
+        store.quads_for_pattern(None, None, Some(iri.into()), graph),
+    ] {
+        if let Some(quad) = quads.into_iter().next() {
+            quad?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/**
+ * The classes and properties (and the kinds of shapes)
+ * the name of `shape` was minted for.
+ * More than one means that the shapes generated for them
+ * ended up as a single one.
+ */
+fn shape_origins(
+    store_shacl: &Store,
+    config: &Config,
+    shape: &NamedNode,
+) -> Res<Vec<(NamedNode, ShapeKind)>> {
+    let mut candidates = Vec::new();
+    for path in objects(store_shacl, shape.into(), sh::PATH)? {
+        candidates.extend(path_property(store_shacl, &path)?);
+    }
+    for pred in [
+        sh::TARGET_CLASS,
+        sh::TARGET_SUBJECTS_OF,
+        sh::TARGET_OBJECTS_OF,
+    ] {
+        for target in objects(store_shacl, shape.into(), pred)? {
+            if let Term::NamedNode(target) = target {
+                candidates.push(target);
+            }
+        }
+    }
+    let mut origins = Vec::new();
+    for candidate in candidates {
+        for kind in [ShapeKind::Main, ShapeKind::Inverse, ShapeKind::Sparql] {
+            let origin = (candidate.clone(), kind);
+            if shape_iri(config, candidate.as_ref(), kind)? == *shape && !origins.contains(&origin)
+            {
+                origins.push(origin);
+            }
+        }
+    }
+    Ok(origins)
+}
+
+fn report_collision(config: &Config, shape: &NamedNode, msg: String) -> Res<()> {
+    match config.shape_name_collision {
+        OdityHandling::Ignore => {}
+        OdityHandling::Warn => log::warn!("{msg}"),
+        OdityHandling::Error => {
+            return Err(ConversionError::Oddity {
+                iri: Some(shape.to_string()),
+                msg,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_collisions(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
+    if config.shape_name_collision.ignore() {
+        return Ok(());
+    }
+    for shape in named_shapes(store_shacl)? {
+        if is_used(store_owl, &shape)? {
+            let msg = format!(
+                "The IRI of the generated shape {shape} is already used in the source ontology; \
+                consider a different shape naming strategy"
+            );
+            report_collision(config, &shape, msg)?;
+        }
+        let origins = shape_origins(store_shacl, config, &shape)?;
+        if origins.len() > 1 {
+            let origins = origins
+                .iter()
+                .map(|(origin, kind)| format!("{origin} ({kind:?})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let msg = format!(
+                "The IRI of the generated shape {shape} was minted for multiple shapes, \
+                which got merged: {origins}; consider a different shape naming strategy"
+            );
+            report_collision(config, &shape, msg)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn finalize_shape_names(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
+    log::info!("Finalizing shape names ...");
+    if matches!(config.shape_naming, ShapeNaming::BlankNode) {
+        for shape in named_shapes(store_shacl)? {
+            let blank = BlankNode::default();
+            rename_node(store_shacl, shape.as_ref(), blank.as_ref().into())?;
+        }
+    } else {
+        check_collisions(store_owl, store_shacl, config)?;
+    }
+    log::info!("Finalizing shape names - done.");
+
+    Ok(())
+}
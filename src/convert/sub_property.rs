@@ -0,0 +1,161 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Converts `rdfs:subPropertyOf` hierarchies:
+//! sub-properties inherit the domains and ranges of their super-properties,
+//! and the shapes of super-properties may also match
+//! the values of their sub-properties.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use const_format::concatcp;
+use once_cell::sync::Lazy;
+use oxigraph::model::{BlankNode, GraphName, GraphNameRef, NamedNode, Quad, QuadRef, Term};
+use oxigraph::sparql::{Query, QueryResults};
+use oxigraph::store::Store;
+
+use super::{
+    as_named, copy_term, domains, get_required, handle_axiom_result, insert_list, own_specs,
+    property_shape, ranges, Res, QUERY_PRELUDE,
+};
+use crate::config::{Config, Inheritance, RDProperty};
+use crate::vocab::sh;
+
+const QS_SUB_PROPERTIES: &str = r"
+SELECT ?sub ?super
+WHERE {
+    ?sub rdfs:subPropertyOf+ ?super .
+    FILTER ( ?sub != ?super )
+}
+ORDER BY ?super ?sub
+";
+query_parser!(Q_SUB_PROPERTIES, QS_SUB_PROPERTIES);
+
+fn inherit(
+    store_shacl: &Store,
+    prop: RDProperty,
+    sub_shape: &NamedNode,
+    super_shape: &NamedNode,
+) -> Res<()> {
+    match prop {
+        RDProperty::Range => {
+            for (pred, range) in ranges(store_shacl, super_shape)? {
+                // Blank ranges (e.g. `sh:or` lists) must not be shared between shapes
+                let range = copy_term(store_shacl, &range)?;
+                ins!(store_shacl, sub_shape.as_ref(), pred, range);
+            }
+        }
+        RDProperty::Domain => {
+            for domain in domains(store_shacl, super_shape)? {
+                let Term::NamedNode(domain) = domain else {
+                    continue;
+                };
+                ins!(
+                    store_shacl,
+                    domain.as_ref(),
+                    sh::PROPERTY,
+                    sub_shape.as_ref()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Replaces the `sh:path` of the property shape `super_shape`
+ * with an `sh:alternativePath` of `super_prop` and all its sub-properties.
+ */
+fn insert_alternative_path(
+    store_shacl: &Store,
+    super_prop: &NamedNode,
+    super_shape: &NamedNode,
+    sub_props: &BTreeSet<NamedNode>,
+) -> Res<()> {
+    store_shacl.remove(QuadRef::new(
+        super_shape,
+        sh::PATH,
+        super_prop,
+        GraphNameRef::DefaultGraph,
+    ))?;
+    let alternatives = insert_list(
+        store_shacl,
+        std::iter::once(super_prop)
+            .chain(sub_props)
+            .map(|prop| prop.clone().into()),
+    )?;
+    let path = BlankNode::default();
+    ins!(store_shacl, super_shape.as_ref(), sh::PATH, path.as_ref());
+    ins!(
+        store_shacl,
+        path.as_ref(),
+        sh::ALTERNATIVE_PATH,
+        alternatives
+    );
+    Ok(())
+}
+
+pub fn convert_sub_properties(store_owl: &Store, store_shacl: &Store, config: &Config) -> Res<()> {
+    log::info!("Converting sub-properties ...");
+    // super-property -> all its (transitive) sub-properties
+    let mut hierarchy: BTreeMap<NamedNode, BTreeSet<NamedNode>> = BTreeMap::new();
+    if let QueryResults::Solutions(solutions) = store_owl.query(Q_SUB_PROPERTIES.to_owned())? {
+        for sol_res in solutions {
+            let sol = sol_res?;
+            let sub = get_required(&sol, "sub")?;
+            let res = as_named(get_required(&sol, "super")?, "super-property")
+                .and_then(|super_prop| Ok((super_prop, as_named(sub, "sub-property")?)));
+            if let Some((super_prop, sub)) = handle_axiom_result(config, sub, res)? {
+                hierarchy
+                    .entry(super_prop.into_owned())
+                    .or_default()
+                    .insert(sub.into_owned());
+            }
+        }
+    }
+
+    // Only the sub-properties own domains and ranges count
+    // when deciding whether to inherit, so we collect them up-front.
+    let mut own_specs_cache = HashMap::new();
+    for sub in hierarchy.values().flatten() {
+        if let Some(sub_shape) = property_shape(store_shacl, config, sub)? {
+            let specs = own_specs(store_shacl, &sub_shape)?;
+            own_specs_cache.insert(sub_shape, specs);
+        }
+    }
+
+    for (super_prop, sub_props) in &hierarchy {
+        let Some(super_shape) = property_shape(store_shacl, config, super_prop)? else {
+            continue;
+        };
+        log::info!("Sub-properties of {super_prop}");
+        for sub in sub_props {
+            let Some(sub_shape) = property_shape(store_shacl, config, sub)? else {
+                continue;
+            };
+            let own = own_specs_cache.get(&sub_shape).copied().unwrap_or_default();
+            for (prop, inheritance) in config.sub_property_inheritance {
+                let do_inherit = match inheritance {
+                    Inheritance::None => false,
+                    Inheritance::IfMissing => !own[prop],
+                    Inheritance::Always => true,
+                };
+                if do_inherit {
+                    inherit(store_shacl, prop, &sub_shape, &super_shape)?;
+                }
+            }
+        }
+    }
+
+    if config.sub_property_alternative_path {
+        for (super_prop, sub_props) in &hierarchy {
+            if let Some(super_shape) = property_shape(store_shacl, config, super_prop)? {
+                insert_alternative_path(store_shacl, super_prop, &super_shape, sub_props)?;
+            }
+        }
+    }
+    log::info!("Converting sub-properties - done.");
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::io;
+
+use oxigraph::model::IriParseError;
+use oxigraph::sparql::EvaluationError;
+use oxigraph::store::StorageError;
+use thiserror::Error;
+
+/**
+ * What may go wrong when converting an OWL ontology into SHACL shapes.
+ */
+#[derive(Error, Debug)]
+pub enum ConversionError {
+    /// The source ontology uses a construct
+    /// that can not (yet) be translated into SHACL.
+    #[error("Unsupported construct at {iri}: {what}")]
+    UnsupportedConstruct { iri: String, what: String },
+
+    /// A class or property is a blank node,
+    /// but only named nodes can be converted into shapes.
+    #[error("Only named-node subjects are supported, but found blank node {node} as {role}")]
+    BlankNodeSubject { node: String, role: &'static str },
+
+    /// An IRI was constructed or found, that is not valid.
+    #[error("Invalid IRI '{iri}': {source}")]
+    InvalidIri {
+        iri: String,
+        #[source]
+        source: IriParseError,
+    },
+
+    /// A result of one of our SPARQL queries lacks a required variable.
+    #[error("SPARQL result is missing the required variable ?{var}")]
+    MissingBinding { var: &'static str },
+
+    /// A result of one of our SPARQL queries binds a variable
+    /// to an unexpected kind of value.
+    #[error("SPARQL result for {iri} binds ?{var} to {value}, which is not {expected}")]
+    UnexpectedBinding {
+        iri: String,
+        var: String,
+        value: String,
+        expected: &'static str,
+    },
+
+    /// An oddity was detected in the source ontology,
+    /// and it is configured to be handled as an error.
+    #[error("{msg}")]
+    Oddity { iri: Option<String>, msg: String },
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error(transparent)]
+    Query(#[from] EvaluationError),
+}
+
+/**
+ * What may go wrong when loading an ontology into a store.
+ */
+#[derive(Error, Debug)]
+pub enum LoadError {
+    /// The base IRI to resolve relative IRIs against is not valid.
+    #[error("Invalid base IRI '{iri}': {source}")]
+    InvalidBaseIri {
+        iri: String,
+        #[source]
+        source: IriParseError,
+    },
+
+    /// The ontology is not valid in the RDF serialization it is parsed as.
+    #[error("Syntax error{}: {msg}", position(*.line, *.byte))]
+    Syntax {
+        line: Option<u64>,
+        byte: Option<u64>,
+        msg: String,
+    },
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+fn position(line: Option<u64>, byte: Option<u64>) -> String {
+    match (line, byte) {
+        (Some(line), Some(byte)) => format!(" at line {line}, byte {byte}"),
+        (Some(line), None) => format!(" at line {line}"),
+        (None, Some(_) | None) => String::new(),
+    }
+}
+
+impl ConversionError {
+    /**
+     * Whether this error is restricted to a single axiom,
+     * which can thus be skipped while converting the rest of the ontology.
+     */
+    #[must_use]
+    pub const fn is_axiom_local(&self) -> bool {
+        match self {
+            Self::UnsupportedConstruct { .. }
+            | Self::BlankNodeSubject { .. }
+            | Self::InvalidIri { .. }
+            | Self::MissingBinding { .. }
+            | Self::UnexpectedBinding { .. } => true,
+            Self::Oddity { .. } | Self::Storage(_) | Self::Query(_) => false,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Tries to convert simple OWL ontologies into SHACL shapes.
+//!
+//! ```no_run
+//! use oxigraph::store::Store;
+//! use owl2shacl::{Config, Converter};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! let store_owl = Store::new()?;
+//! // ... load your ontology into `store_owl` ...
+//! let converter = Converter::new(Config::default());
+//! let store_shacl = converter.convert(&store_owl)?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod cardinality;
+pub mod config;
+mod convert;
+mod error;
+pub mod load;
+pub mod naming;
+pub mod serialize;
+pub mod vocab;
+
+pub use config::Config;
+pub use convert::Converter;
+pub use error::{ConversionError, LoadError};
@@ -0,0 +1,523 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Loads ontologies in any of the supported RDF serializations into a store.
+//!
+//! See [`InputFormat`] for the supported serializations.
+//! If the serialization is not known,
+//! it can be guessed from the start of the content with [`sniff_format`].
+//!
+//! This parses with the same `rio` parsers Oxigraph's own loader uses,
+//! instead of [`Store::load_graph`] and [`Store::load_dataset`],
+//! as those offer no way to
+//!
+//! - capture the prefix declarations, which are reused for writing the shapes, and
+//! - load only the default graph plus a selection of named graphs.
+//!
+//! The `oxiri`, `rio_api`, `rio_turtle` and `rio_xml` dependencies
+//! thus have to stay at the (major) versions used by the `oxigraph` dependency.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read};
+
+use oxigraph::model::{
+    BlankNode, GraphNameRef, Literal, NamedNode, QuadRef, Subject, Term, Triple,
+};
+use oxigraph::store::{StorageError, Store};
+use oxiri::Iri;
+use rio_api::model as rio;
+use rio_api::parser::{LineBytePosition, ParseError, QuadsParser, TriplesParser};
+use rio_turtle::{NQuadsParser, NTriplesParser, TriGParser, TurtleError, TurtleParser};
+use rio_xml::{RdfXmlError, RdfXmlParser};
+
+use crate::config::InputFormat;
+use crate::error::LoadError;
+use crate::serialize::Prefixes;
+
+/**
+ * Counts the lines consumed from the wrapped reader,
+ * to locate the errors of parsers that do not report a position themselves
+ * (e.g. the RDF/XML one).
+ */
+struct LineCountingReader<'a, R> {
+    inner: R,
+    lines: &'a Cell<usize>,
+}
+
+#[allow(clippy::naive_bytecount)]
+fn count_lines(lines: &Cell<usize>, bytes: &[u8]) {
+    let new_lines = bytes.iter().filter(|byte| **byte == b'\n').count();
+    lines.set(lines.get() + new_lines);
+}
+
+impl<R: BufRead> Read for LineCountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        count_lines(self.lines, buf.get(..read).unwrap_or_default());
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for LineCountingReader<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // This does no I/O, as the buffer was filled before consuming from it
+        if let Ok(buf) = self.inner.fill_buf() {
+            count_lines(self.lines, buf.get(..amt).unwrap_or(buf));
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/**
+ * Why parsing stopped.
+ */
+enum Failure {
+    Syntax {
+        position: Option<LineBytePosition>,
+        msg: String,
+    },
+    Storage(StorageError),
+}
+
+impl From<TurtleError> for Failure {
+    fn from(err: TurtleError) -> Self {
+        let position = err.textual_position();
+        let mut msg = err.to_string();
+        if let Some(position) = position {
+            // The position gets reported separately, see `LoadError::Syntax`
+            let suffix = format!(
+                " on line {} at position {}",
+                position.line_number(),
+                position.byte_number()
+            );
+            if msg.ends_with(&suffix) {
+                msg.truncate(msg.len() - suffix.len());
+            }
+        }
+        Self::Syntax { position, msg }
+    }
+}
+
+impl From<RdfXmlError> for Failure {
+    fn from(err: RdfXmlError) -> Self {
+        Self::Syntax {
+            position: err.textual_position(),
+            msg: err.to_string(),
+        }
+    }
+}
+
+impl From<StorageError> for Failure {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err)
+    }
+}
+
+/**
+ * Inserts the parsed triples into the default graph of a store,
+ * with fresh blank nodes for the blank node labels of each loaded ontology.
+ */
+struct Loader<'a> {
+    store: &'a Store,
+    graphs: &'a [NamedNode],
+    blank_nodes: HashMap<String, BlankNode>,
+    skipped: usize,
+}
+
+impl Loader<'_> {
+    fn named_node(node: rio::NamedNode) -> NamedNode {
+        NamedNode::new_unchecked(node.iri)
+    }
+
+    fn blank_node(&mut self, node: rio::BlankNode) -> BlankNode {
+        self.blank_nodes
+            .entry(node.id.to_owned())
+            .or_default()
+            .clone()
+    }
+
+    fn literal(literal: rio::Literal) -> Literal {
+        match literal {
+            rio::Literal::Simple { value } => Literal::new_simple_literal(value),
+            rio::Literal::LanguageTaggedString { value, language } => {
+                Literal::new_language_tagged_literal_unchecked(value, language.to_ascii_lowercase())
+            }
+            rio::Literal::Typed { value, datatype } => {
+                Literal::new_typed_literal(value, Self::named_node(datatype))
+            }
+        }
+    }
+
+    fn subject(&mut self, subject: rio::Subject) -> Subject {
+        match subject {
+            rio::Subject::NamedNode(node) => Self::named_node(node).into(),
+            rio::Subject::BlankNode(node) => self.blank_node(node).into(),
+            rio::Subject::Triple(triple) => self.triple(*triple).into(),
+        }
+    }
+
+    fn term(&mut self, term: rio::Term) -> Term {
+        match term {
+            rio::Term::NamedNode(node) => Self::named_node(node).into(),
+            rio::Term::BlankNode(node) => self.blank_node(node).into(),
+            rio::Term::Literal(literal) => Self::literal(literal).into(),
+            rio::Term::Triple(triple) => self.triple(*triple).into(),
+        }
+    }
+
+    fn triple(&mut self, triple: rio::Triple) -> Triple {
+        Triple::new(
+            self.subject(triple.subject),
+            Self::named_node(triple.predicate),
+            self.term(triple.object),
+        )
+    }
+
+    fn insert(&self, triple: &Triple) -> Result<(), Failure> {
+        self.store.insert(QuadRef::new(
+            &triple.subject,
+            &triple.predicate,
+            &triple.object,
+            GraphNameRef::DefaultGraph,
+        ))?;
+        Ok(())
+    }
+
+    fn insert_triple(&mut self, triple: rio::Triple) -> Result<(), Failure> {
+        let triple = self.triple(triple);
+        self.insert(&triple)
+    }
+
+    /**
+     * Inserts the triple of `quad`,
+     * if it is in the default graph or one of the selected named graphs.
+     */
+    fn insert_quad(&mut self, quad: rio::Quad) -> Result<(), Failure> {
+        let selected = match quad.graph_name {
+            None => true,
+            Some(rio::GraphName::NamedNode(graph)) => {
+                self.graphs.is_empty() || self.graphs.iter().any(|sel| sel.as_str() == graph.iri)
+            }
+            Some(rio::GraphName::BlankNode(_)) => self.graphs.is_empty(),
+        };
+        if !selected {
+            self.skipped += 1;
+            return Ok(());
+        }
+        let triple = self.triple(rio::Triple {
+            subject: quad.subject,
+            predicate: quad.predicate,
+            object: quad.object,
+        });
+        self.insert(&triple)
+    }
+}
+
+fn add_prefixes(prefixes: &mut Prefixes, declared: &HashMap<String, String>) {
+    let mut declared = declared.iter().collect::<Vec<_>>();
+    declared.sort();
+    for (prefix, namespace) in declared {
+        prefixes.insert(prefix, namespace);
+    }
+}
+
+/**
+ * The namespace declarations of the root element of an RDF/XML document,
+ * as far as it is within `start`,
+ * skipping those using entities (e.g. `xmlns:owl="&owl;"`).
+ */
+fn xml_namespaces(start: &[u8]) -> HashMap<String, String> {
+    let text = String::from_utf8_lossy(start);
+    let mut rest = text.as_ref();
+    let root = loop {
+        let Some((_, markup)) = rest.split_once('<') else {
+            return HashMap::new();
+        };
+        let skipped = if let Some(comment) = markup.strip_prefix("!--") {
+            comment.split_once("-->")
+        } else if markup.starts_with("!DOCTYPE") && markup.contains('[') {
+            // A document type declaration with entity definitions
+            markup.split_once("]>")
+        } else if markup.starts_with(['?', '!']) {
+            markup.split_once('>')
+        } else {
+            break markup.split_once('>').map_or(markup, |(root, _)| root);
+        };
+        let Some((_, after)) = skipped else {
+            return HashMap::new();
+        };
+        rest = after;
+    };
+    root.split("xmlns")
+        .skip(1)
+        .filter_map(|declaration| {
+            let (prefix, value) = declaration.split_once('=')?;
+            let prefix = match prefix.trim_end() {
+                "" => "",
+                prefix => prefix.strip_prefix(':')?,
+            };
+            let value = value.trim_start();
+            let quote = value
+                .chars()
+                .next()
+                .filter(|chr| matches!(chr, '"' | '\''))?;
+            let (namespace, _) = value.strip_prefix(quote)?.split_once(quote)?;
+            (!namespace.contains('&')).then(|| (prefix.to_owned(), namespace.to_owned()))
+        })
+        .collect()
+}
+
+/**
+ * Loads an ontology in the RDF serialization `format` from `reader`
+ * into the default graph of `store`.
+ * Of N-Quads and `TriG`, the default graph
+ * and the named graphs `graphs` - or all of them, if empty - are loaded.
+ * The prefixes declared in Turtle and `TriG` get added to `prefixes`,
+ * so they can be reused for writing the shapes.
+ *
+ * # Errors
+ *
+ * If `base_iri` is invalid, the ontology can not be read or parsed,
+ * or the store fails.
+ */
+pub fn load(
+    store: &Store,
+    reader: impl BufRead,
+    format: InputFormat,
+    base_iri: Option<&str>,
+    graphs: &[NamedNode],
+    prefixes: &mut Prefixes,
+) -> Result<(), LoadError> {
+    let base_iri = base_iri
+        .map(|iri| {
+            Iri::parse(iri.to_owned()).map_err(|source| LoadError::InvalidBaseIri {
+                iri: iri.to_owned(),
+                source,
+            })
+        })
+        .transpose()?;
+    let lines = Cell::new(0);
+    let reader = LineCountingReader {
+        inner: reader,
+        lines: &lines,
+    };
+    let mut loader = Loader {
+        store,
+        graphs,
+        blank_nodes: HashMap::new(),
+        skipped: 0,
+    };
+    let result = match format {
+        InputFormat::Turtle => {
+            let mut parser = TurtleParser::new(reader, base_iri);
+            let result = parser.parse_all(&mut |triple| loader.insert_triple(triple));
+            add_prefixes(prefixes, parser.prefixes());
+            result
+        }
+        InputFormat::NTriples => {
+            NTriplesParser::new(reader).parse_all(&mut |triple| loader.insert_triple(triple))
+        }
+        InputFormat::NQuads => {
+            NQuadsParser::new(reader).parse_all(&mut |quad| loader.insert_quad(quad))
+        }
+        InputFormat::TriG => {
+            let mut parser = TriGParser::new(reader, base_iri);
+            let result = parser.parse_all(&mut |quad| loader.insert_quad(quad));
+            add_prefixes(prefixes, parser.prefixes());
+            result
+        }
+        InputFormat::RdfXml => {
+            let mut reader = reader;
+            add_prefixes(prefixes, &xml_namespaces(reader.fill_buf()?));
+            RdfXmlParser::new(reader, base_iri)
+                .parse_all(&mut |triple| loader.insert_triple(triple))
+        }
+    };
+    if loader.skipped > 0 {
+        log::info!(
+            "Skipped {} triples of named graphs that were not selected",
+            loader.skipped
+        );
+    }
+    result.map_err(|failure| match failure {
+        Failure::Syntax { position, msg } => LoadError::Syntax {
+            line: position.map_or_else(
+                || u64::try_from(lines.get() + 1).ok(),
+                |position| Some(position.line_number()),
+            ),
+            byte: position.map(|position| position.byte_number()),
+            msg,
+        },
+        Failure::Storage(err) => err.into(),
+    })
+}
+
+/**
+ * Whether `text` starts with an XML element with attributes,
+ * e.g. `<rdf:RDF xmlns:rdf="...">`, as opposed to an IRI,
+ * which can contain neither white-space nor `=`.
+ */
+fn is_xml_element(text: &str) -> bool {
+    text.strip_prefix('<')
+        .and_then(|rest| rest.split_once('>'))
+        .is_some_and(|(tag, _)| tag.contains(|chr: char| chr.is_whitespace() || chr == '='))
+}
+
+/**
+ * The number of terms of the N-Triples or N-Quads statement `line`,
+ * or `None` if it is not one.
+ */
+fn count_terms(line: &str) -> Option<usize> {
+    let mut chars = line.strip_suffix('.')?.chars().peekable();
+    let mut count = 0;
+    loop {
+        while chars.next_if(|chr| chr.is_whitespace()).is_some() {}
+        match chars.next() {
+            None => return Some(count),
+            Some('<') => {
+                chars.find(|chr| *chr == '>')?;
+            }
+            Some('"') => {
+                let mut escaped = false;
+                chars.find(|chr| {
+                    let end = *chr == '"' && !escaped;
+                    escaped = *chr == '\\' && !escaped;
+                    end
+                })?;
+                // The language tag or datatype
+                while chars.next_if(|chr| !chr.is_whitespace()).is_some() {}
+            }
+            Some('_') if chars.next_if_eq(&':').is_some() => {
+                while chars.next_if(|chr| !chr.is_whitespace()).is_some() {}
+            }
+            Some(_) => return None,
+        }
+        count += 1;
+    }
+}
+
+/**
+ * Guesses the RDF serialization of an ontology from the start of its content
+ * (e.g. what is available in the buffer of a reader),
+ * falling back to Turtle.
+ */
+#[must_use]
+pub fn sniff_format(start: &[u8]) -> InputFormat {
+    let text = String::from_utf8_lossy(start);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with("<?") || text.starts_with("<!") || is_xml_element(text) {
+        return InputFormat::RdfXml;
+    }
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<_>>();
+    if !start.ends_with(b"\n") {
+        // The last line might be cut off
+        lines.pop();
+    }
+    let is_graph_block = |line: &&str| {
+        line.starts_with('{')
+            || line.ends_with('{')
+            || line
+                .split_whitespace()
+                .next()
+                .is_some_and(|word| word.eq_ignore_ascii_case("GRAPH"))
+    };
+    if lines.iter().any(is_graph_block) {
+        return InputFormat::TriG;
+    }
+    let terms = lines
+        .iter()
+        .map(|line| count_terms(line))
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default();
+    if terms.is_empty() || !terms.iter().all(|count| *count == 3 || *count == 4) {
+        InputFormat::Turtle
+    } else if terms.contains(&4) {
+        InputFormat::NQuads
+    } else {
+        InputFormat::NTriples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_terms_of_statements() {
+        assert_eq!(count_terms("<s> <p> <o> ."), Some(3));
+        assert_eq!(count_terms("<s><p><o>."), Some(3));
+        assert_eq!(count_terms("_:b <p> \"a b .\"@en <g> ."), Some(4));
+        assert_eq!(
+            count_terms("<s> <p> \"say \\\"hi\\\"\"^^<http://www.w3.org/2001/XMLSchema#string> ."),
+            Some(3)
+        );
+        assert_eq!(count_terms("<s> <p> <o>"), None);
+        assert_eq!(count_terms("ex:s ex:p ex:o ."), None);
+        assert_eq!(count_terms("<s> a <o> ."), None);
+        assert_eq!(count_terms("<s> <p> \"unterminated ."), None);
+    }
+
+    #[test]
+    fn sniff_xml() {
+        assert_eq!(
+            sniff_format(b"<?xml version=\"1.0\"?>\n<rdf:RDF/>\n"),
+            InputFormat::RdfXml
+        );
+        assert_eq!(
+            sniff_format(b"\xEF\xBB\xBF<!-- comment -->\n<rdf:RDF/>\n"),
+            InputFormat::RdfXml
+        );
+        assert_eq!(
+            sniff_format(b"<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n"),
+            InputFormat::RdfXml
+        );
+    }
+
+    #[test]
+    fn sniff_line_based() {
+        assert_eq!(
+            sniff_format(b"# comment\n<s> <p> <o> .\n_:b <p> \"o\" .\n"),
+            InputFormat::NTriples
+        );
+        assert_eq!(
+            sniff_format(b"<s> <p> <o> .\n<s> <p> <o> <g> .\n"),
+            InputFormat::NQuads
+        );
+        // The last, cut off line is ignored
+        assert_eq!(
+            sniff_format(b"<s> <p> <o> .\n<s> <p> <o"),
+            InputFormat::NTriples
+        );
+    }
+
+    #[test]
+    fn sniff_turtle_and_trig() {
+        assert_eq!(
+            sniff_format(b"@prefix ex: <https://example.org/> .\nex:s ex:p ex:o .\n"),
+            InputFormat::Turtle
+        );
+        assert_eq!(
+            sniff_format(b"<https://example.org/s> a <https://example.org/C> .\n"),
+            InputFormat::Turtle
+        );
+        assert_eq!(sniff_format(b""), InputFormat::Turtle);
+        assert_eq!(
+            sniff_format(b"@prefix ex: <https://example.org/> .\nex:g {\n  ex:s ex:p ex:o .\n}\n"),
+            InputFormat::TriG
+        );
+        assert_eq!(
+            sniff_format(b"GRAPH <g> { <s> <p> <o> }\n"),
+            InputFormat::TriG
+        );
+    }
+}
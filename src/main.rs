@@ -2,76 +2,117 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-mod config;
-mod convert;
-mod vocab;
+mod cli;
 
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
 
-use config::Config;
+use clap::Parser;
 use log::LevelFilter;
-use oxigraph::io::GraphFormat;
-
-use oxigraph::model::GraphName;
+use oxigraph::model::NamedNode;
 use oxigraph::store::Store;
 
+use owl2shacl::config::{InputFormat, OutputFormat};
+use owl2shacl::load;
+use owl2shacl::serialize::{self, Prefixes};
+use owl2shacl::{Config, Converter};
+
+use cli::Cli;
+
 type Error = Box<dyn std::error::Error + Sync + Send>;
 type Res<O> = Result<O, Error>;
 
-const SAMPLE_OWL_ONTOLOGY_TURTLE_FILE_PATHS: [(&str, &str, &str); 3] = [
-    (
-        "res/ont/okh-losh-snapshot-2024-01-07.ttl",
-        "okh_losh",
-        "https://w3id.org/oseg/ont/okh",
-    ),
-    (
-        "res/ont/vf.ttl",
-        "value_flows",
-        "https://w3id.org/valueflows",
-    ),
-    (
-        "res/ont/test-onto-mult-val.ttl",
-        "test_multi_valued_properties",
-        "https://example.org/ont/testmv",
-    ),
-];
-
-fn load_onto_into_store(store: &mut Store, onto_id: usize) -> Res<()> {
-    if let Some((ont_file, _ont_name, ont_base_iri)) =
-        SAMPLE_OWL_ONTOLOGY_TURTLE_FILE_PATHS.get(onto_id)
+fn load_onto_into_store(
+    store: &Store,
+    prefixes: &mut Prefixes,
+    ont_file: &Path,
+    config: &Config,
+    base_iri: Option<&str>,
+) -> Res<()> {
+    log::info!("Loading '{}' ...", ont_file.display());
+    let mut reader = BufReader::new(
+        File::open(ont_file)
+            .map_err(|err| format!("Failed to open '{}': {err}", ont_file.display()))?,
+    );
+    let format = match config
+        .input_format
+        .or_else(|| InputFormat::from_extension(ont_file))
     {
-        let buf_ttl_ont_reader = BufReader::new(File::open(ont_file)?);
-        // let graph_name = GraphName::NamedNode(NamedNode::new(*ont_base_iri)?);
-        let graph_name = GraphName::DefaultGraph;
-        store.load_graph(
-            buf_ttl_ont_reader,
-            GraphFormat::Turtle,
-            graph_name.as_ref(),
-            Some(ont_base_iri),
-        )?;
+        Some(format) => format,
+        None => load::sniff_format(reader.fill_buf()?),
+    };
+    log::info!(
+        "Parsing '{}' as {} ...",
+        ont_file.display(),
+        <&str>::from(format)
+    );
+    if !config.input_graphs.is_empty() && !format.has_graphs() {
+        log::warn!(
+            "'{}' has no named graphs to select from; loading all of it",
+            ont_file.display()
+        );
     }
+    load::load(
+        store,
+        reader,
+        format,
+        base_iri,
+        &config.input_graphs,
+        prefixes,
+    )
+    .map_err(|err| format!("Failed to load '{}': {err}", ont_file.display()))?;
 
     Ok(())
 }
 
-fn load_source() -> Res<Store> {
-    let mut store_owl = Store::new()?;
+fn load_source(cli: &Cli, config: &Config) -> Res<(Store, Prefixes)> {
+    let store_owl = Store::new()?;
+    let mut prefixes = Prefixes::new();
     log::info!("Loading ...");
-    load_onto_into_store(&mut store_owl, 1)?;
+    for ont_file in &cli.inputs {
+        load_onto_into_store(
+            &store_owl,
+            &mut prefixes,
+            ont_file,
+            config,
+            cli.base.as_deref(),
+        )?;
+    }
     log::info!("Loaded.");
     log::info!("store_owl len: {}", store_owl.len()?);
     log::info!("Optimizing ...");
     store_owl.optimize()?;
     log::info!("Optimized.");
-    Ok(store_owl)
+    Ok((store_owl, prefixes))
 }
 
 fn main() -> Res<()> {
-    env_logger::builder().filter_level(LevelFilter::Info).init();
-    // oxigraph_query_example()
-    let store_owl = load_source()?;
-    let config = Config::default();
-    convert::convert(&store_owl, &config)
-    // construct_convert()
+    env_logger::builder()
+        .filter_level(LevelFilter::Info)
+        .parse_default_env()
+        .init();
+    let cli = Cli::parse();
+    let config = cli.to_config();
+    let (store_owl, prefixes) = load_source(&cli, &config)?;
+    let format = config
+        .output_format
+        .or_else(|| cli.output_file().and_then(OutputFormat::from_extension))
+        .unwrap_or_default();
+    let shapes_graph = config.shapes_graph.clone();
+    let converter = Converter::new(config);
+    let store_shacl = converter.convert(&store_owl)?;
+    let mut output: Box<dyn Write> = match cli.output_file() {
+        Some(out_file) => Box::new(BufWriter::new(File::create(out_file)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+    serialize::write(
+        &store_shacl,
+        format,
+        &prefixes,
+        shapes_graph.as_ref().map(NamedNode::as_ref),
+        &mut output,
+    )?;
+    output.flush()?;
+    Ok(())
 }
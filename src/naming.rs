@@ -0,0 +1,176 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Strategies for naming the generated shapes,
+//! based on the IRI of the class or property they are generated for.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use oxigraph::model::NamedNodeRef;
+
+/**
+ * What a shape is generated for,
+ * which distinguishes the multiple shapes generated for a single property.
+ */
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ShapeKind {
+    /// The node shape of a class, or the property shape of a property
+    Main,
+    /// The shape constraining the subjects pointing to the values
+    /// of an inverse-functional property
+    Inverse,
+    /// The shape holding the SHACL-SPARQL constraints of a property
+    Sparql,
+}
+
+impl ShapeKind {
+    /**
+     * A tag to distinguish the names of the different kinds of shapes
+     * generated for the same class or property.
+     */
+    #[must_use]
+    pub const fn tag(self) -> &'static str {
+        match self {
+            Self::Main => "",
+            Self::Inverse => "Inverse",
+            Self::Sparql => "Sparql",
+        }
+    }
+}
+
+/**
+ * A custom way of naming shapes; see [`ShapeNaming::Custom`].
+ */
+pub trait MintShapeIri: fmt::Debug + Send + Sync {
+    /**
+     * Returns the IRI of the shape of kind `kind`
+     * generated for the class or property `orig`.
+     * Different inputs have to result in different IRIs.
+     */
+    fn mint(&self, orig: NamedNodeRef, kind: ShapeKind) -> String;
+}
+
+/**
+ * How to name the generated shapes.
+ */
+#[derive(Debug, Clone)]
+pub enum ShapeNaming {
+    /// Append a suffix to the IRI of the class or property,
+    /// e.g. `ex:Person` -> `ex:PersonShape`
+    Suffix(String),
+    /// Put a prefix in front of the local name of the class or property,
+    /// e.g. `ex:Person` -> `ex:Shape_Person`
+    Prefix(String),
+    /// Move the shapes into a separate namespace,
+    /// e.g. `vf:EconomicEvent` -> `vfs:EconomicEvent`;
+    /// classes and properties from other namespaces
+    /// get the default suffix instead.
+    Namespace { from: String, to: String },
+    /// Use a hash of the IRI of the class or property as local name,
+    /// within the given namespace, or else the namespace of the class or property,
+    /// e.g. `ex:Person` -> `ex:shape-<16 hex digits>`
+    Hash(Option<String>),
+    /// Make all the shapes blank nodes
+    BlankNode,
+    /// A custom strategy
+    Custom(Arc<dyn MintShapeIri>),
+}
+
+/// The suffix of [`ShapeNaming::Suffix`] by default.
+pub const DEFAULT_SUFFIX: &str = "Shape";
+
+impl Default for ShapeNaming {
+    fn default() -> Self {
+        Self::Suffix(DEFAULT_SUFFIX.to_owned())
+    }
+}
+
+/**
+ * Splits `iri` into namespace and local name,
+ * after the last `#` or `/`.
+ */
+pub(crate) fn split_iri(iri: &str) -> (&str, &str) {
+    iri.rfind(['#', '/'])
+        .map_or(("", iri), |pos| iri.split_at(pos + 1))
+}
+
+/**
+ * The 64 bit FNV-1a hash of `data`,
+ * which - unlike the hashers of `std` - is stable across builds.
+ */
+fn fnv1a(data: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    data.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+impl ShapeNaming {
+    /**
+     * Returns the IRI of the shape of kind `kind`
+     * generated for the class or property `orig`.
+     * For [`Self::BlankNode`], this is the IRI the shape uses
+     * while converting, before it gets replaced by a blank node.
+     */
+    #[must_use]
+    pub fn mint(&self, orig: NamedNodeRef, kind: ShapeKind) -> String {
+        let iri = orig.as_str();
+        let tag = kind.tag();
+        match self {
+            Self::Suffix(suffix) => format!("{iri}{tag}{suffix}"),
+            Self::Prefix(prefix) => {
+                let (namespace, local) = split_iri(iri);
+                format!("{namespace}{prefix}{local}{tag}")
+            }
+            Self::Namespace { from, to } => iri.strip_prefix(from.as_str()).map_or_else(
+                || format!("{iri}{tag}{DEFAULT_SUFFIX}"),
+                |local| format!("{to}{local}{tag}"),
+            ),
+            Self::Hash(namespace) => {
+                let namespace = namespace.as_deref().unwrap_or_else(|| split_iri(iri).0);
+                format!("{namespace}shape-{:016x}", fnv1a(&format!("{iri} {tag}")))
+            }
+            Self::BlankNode => Self::default().mint(orig, kind),
+            Self::Custom(minter) => minter.mint(orig, kind),
+        }
+    }
+}
+
+impl FromStr for ShapeNaming {
+    type Err = String;
+
+    /**
+     * Parses one of `suffix[:SUFFIX]`, `prefix:PREFIX`,
+     * `namespace:FROM=TO`, `hash[:NAMESPACE]` and `blank-node`.
+     */
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (strategy, arg) = match value.split_once(':') {
+            Some((strategy, arg)) => (strategy, Some(arg)),
+            None => (value, None),
+        };
+        match (strategy, arg) {
+            ("suffix", None) => Ok(Self::default()),
+            ("suffix", Some(suffix)) => Ok(Self::Suffix(suffix.to_owned())),
+            ("prefix", Some(prefix)) => Ok(Self::Prefix(prefix.to_owned())),
+            ("namespace", Some(namespaces)) => match namespaces.split_once('=') {
+                Some((from, to)) => Ok(Self::Namespace {
+                    from: from.to_owned(),
+                    to: to.to_owned(),
+                }),
+                None => Err(format!(
+                    "Invalid namespace rewrite '{namespaces}'; expected FROM=TO"
+                )),
+            },
+            ("hash", namespace) => Ok(Self::Hash(namespace.map(ToOwned::to_owned))),
+            ("blank-node", None) => Ok(Self::BlankNode),
+            _ => Err(format!(
+                "Invalid shape naming '{value}'; valid values are: \
+                suffix[:SUFFIX], prefix:PREFIX, namespace:FROM=TO, hash[:NAMESPACE], blank-node"
+            )),
+        }
+    }
+}
@@ -0,0 +1,799 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Writes the generated shapes in one of the [`OutputFormat`]s.
+//!
+//! Turtle, `TriG` and JSON-LD are written human readable:
+//! IRIs are abbreviated with prefixes,
+//! the triples of each shape are grouped together,
+//! blank nodes and lists are nested where possible,
+//! and everything is sorted, so the output is stable across runs.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{self, Write};
+
+use oxigraph::io::{GraphFormat, GraphSerializer};
+use oxigraph::model::vocab::{rdf, xsd};
+use oxigraph::model::{
+    BlankNode, Graph, GraphNameRef, Literal, NamedNode, NamedNodeRef, Subject, SubjectRef, Term,
+    Triple,
+};
+use oxigraph::store::{StorageError, Store};
+
+use crate::config::OutputFormat;
+use crate::vocab::sh;
+
+mod jsonld;
+
+/**
+ * Prefix declarations, mapping prefix names to namespace IRIs,
+ * e.g. `sh` to `http://www.w3.org/ns/shacl#`.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Prefixes(BTreeMap<String, String>);
+
+impl Prefixes {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Adds a prefix declaration, unless the prefix name is already declared;
+     * the first declaration wins.
+     */
+    pub fn insert(&mut self, prefix: impl Into<String>, namespace: impl Into<String>) {
+        let prefix = prefix.into();
+        let namespace = namespace.into();
+        match self.0.get(&prefix) {
+            Some(existing) if *existing != namespace => log::warn!(
+                "Ignoring the declaration of the prefix '{prefix}:' as <{namespace}>, \
+                as it is already declared as <{existing}>"
+            ),
+            Some(_) => {}
+            None => {
+                self.0.insert(prefix, namespace);
+            }
+        }
+    }
+
+    /**
+     * Whether `namespace` is declared under any prefix name.
+     */
+    #[must_use]
+    pub fn has_namespace(&self, namespace: &str) -> bool {
+        self.0.values().any(|declared| declared == namespace)
+    }
+
+    /**
+     * Iterates over the prefix names and namespaces, sorted by prefix name.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(prefix, namespace)| (prefix.as_str(), namespace.as_str()))
+    }
+
+    /**
+     * The prefixes that can be used as terms in a JSON-LD context.
+     */
+    fn for_json_ld(&self) -> Self {
+        Self(
+            self.0
+                .iter()
+                .filter(|(prefix, _)| !prefix.is_empty() && *prefix != "_")
+                .map(|(prefix, namespace)| (prefix.clone(), namespace.clone()))
+                .collect(),
+        )
+    }
+
+    /**
+     * Abbreviates `iri` with the prefix of the longest matching namespace,
+     * if the remaining local name can be written as such in Turtle.
+     */
+    fn abbreviate<'a>(&'a self, iri: &'a str) -> Option<(&'a str, &'a str)> {
+        self.iter()
+            .filter_map(|(prefix, namespace)| {
+                let local = iri.strip_prefix(namespace)?;
+                is_local_name(local).then_some((prefix, namespace.len(), local))
+            })
+            .max_by_key(|(prefix, namespace_len, _)| (*namespace_len, std::cmp::Reverse(*prefix)))
+            .map(|(prefix, _, local)| (prefix, local))
+    }
+}
+
+impl<P: Into<String>, N: Into<String>> FromIterator<(P, N)> for Prefixes {
+    fn from_iter<I: IntoIterator<Item = (P, N)>>(iter: I) -> Self {
+        let mut prefixes = Self::new();
+        for (prefix, namespace) in iter {
+            prefixes.insert(prefix, namespace);
+        }
+        prefixes
+    }
+}
+
+/**
+ * Whether `local` can be written as the local part of a prefixed name
+ * without escaping.
+ * This is more restrictive than Turtle itself.
+ */
+fn is_local_name(local: &str) -> bool {
+    let valid_char = |chr: char| chr.is_ascii_alphanumeric() || matches!(chr, '_' | '-' | '.');
+    local.chars().all(valid_char) && !local.starts_with(['-', '.']) && !local.ends_with('.')
+}
+
+/**
+ * The predicates written first (in this order) within each subject,
+ * followed by all others, sorted.
+ */
+const LEADING_PREDICATES: [NamedNodeRef<'static>; 5] = [
+    rdf::TYPE,
+    sh::TARGET_CLASS,
+    sh::TARGET_SUBJECTS_OF,
+    sh::TARGET_OBJECTS_OF,
+    sh::PATH,
+];
+
+/**
+ * The predicates of the targets of a shape.
+ */
+const TARGET_PREDICATES: [NamedNodeRef<'static>; 3] = [
+    sh::TARGET_CLASS,
+    sh::TARGET_SUBJECTS_OF,
+    sh::TARGET_OBJECTS_OF,
+];
+
+const INDENT: &str = "    ";
+
+/**
+ * The position of `predicate` among the predicates of a subject,
+ * before sorting by the (abbreviated) predicate itself.
+ */
+fn predicate_rank(predicate: &NamedNode) -> usize {
+    LEADING_PREDICATES
+        .iter()
+        .position(|leading| leading == predicate)
+        .unwrap_or(LEADING_PREDICATES.len())
+}
+
+/**
+ * All the triples, grouped by subject,
+ * plus what is needed to decide how to write them
+ * in one of the human readable formats.
+ */
+struct PrettyWriter<'a> {
+    prefixes: &'a Prefixes,
+    about: HashMap<Subject, Vec<(NamedNode, Term)>>,
+    /// Blank nodes that get written nested into the one triple referring to them
+    nested: HashSet<BlankNode>,
+    /// Labels of the blank nodes that can not be nested, assigned in order of appearance
+    labels: RefCell<HashMap<BlankNode, String>>,
+    /// The prefixes actually used
+    used_prefixes: RefCell<BTreeSet<String>>,
+    /// Whether to write blank node labels and note the used prefixes
+    /// (`false` while computing sort keys)
+    with_labels: Cell<bool>,
+}
+
+impl<'a> PrettyWriter<'a> {
+    fn new(
+        store: &Store,
+        graph: GraphNameRef,
+        prefixes: &'a Prefixes,
+    ) -> Result<Self, StorageError> {
+        let mut about: HashMap<Subject, Vec<(NamedNode, Term)>> = HashMap::new();
+        let mut parents: HashMap<BlankNode, Vec<Subject>> = HashMap::new();
+        // Canonical blank nodes break the ties between otherwise equal sort keys
+        for triple in &canonical_graph(store, graph)? {
+            let triple = triple.into_owned();
+            if let Term::BlankNode(object) = &triple.object {
+                parents
+                    .entry(object.clone())
+                    .or_default()
+                    .push(triple.subject.clone());
+            }
+            about
+                .entry(triple.subject)
+                .or_default()
+                .push((triple.predicate, triple.object));
+        }
+        let nested = parents
+            .iter()
+            .filter(|(node, _)| !is_in_cycle(&parents, node))
+            .filter(|(node, _)| !is_target_shape(&about, node))
+            .filter(|(_, node_parents)| node_parents.len() == 1)
+            .map(|(node, _)| node.clone())
+            .collect();
+        Ok(Self {
+            prefixes,
+            about,
+            nested,
+            labels: RefCell::default(),
+            used_prefixes: RefCell::default(),
+            with_labels: Cell::new(true),
+        })
+    }
+
+    /**
+     * Runs `write` writing `_:` for all blank node labels,
+     * which makes for sort keys that do not depend on the labels.
+     */
+    fn without_labels<T>(&self, write: impl FnOnce() -> T) -> T {
+        let with_labels = self.with_labels.replace(false);
+        let written = write();
+        self.with_labels.set(with_labels);
+        written
+    }
+
+    /**
+     * `objects`, sorted by how they get written, but without blank node labels,
+     * as those are only assigned while writing, in order of appearance.
+     * Labelled blank nodes are sorted by what is written about them
+     * (unless already computing a sort key, which could lead into cycles),
+     * and remaining ties are broken by the canonical blank nodes.
+     */
+    fn sort_objects<'t>(&self, objects: impl IntoIterator<Item = &'t Term>) -> Vec<&'t Term> {
+        let expand_labelled = self.with_labels.get();
+        let mut keyed = objects
+            .into_iter()
+            .map(|object| {
+                let key = self.without_labels(|| match object {
+                    Term::BlankNode(node) if expand_labelled && !self.nested.contains(node) => {
+                        format!("_: {}", self.write_predicate_objects(node.into(), 0))
+                    }
+                    Term::NamedNode(_)
+                    | Term::BlankNode(_)
+                    | Term::Literal(_)
+                    | Term::Triple(_) => self.write_term(object, 0),
+                });
+                ((key, object.to_string()), object)
+            })
+            .collect::<Vec<_>>();
+        keyed.sort_by(|(key_a, _), (key_b, _)| key_a.cmp(key_b));
+        keyed.into_iter().map(|(_, object)| object).collect()
+    }
+
+    fn predicate_objects(&self, subject: SubjectRef) -> &[(NamedNode, Term)] {
+        self.about
+            .get(&subject.into_owned())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn write_iri(&self, iri: NamedNodeRef) -> String {
+        if let Some((prefix, local)) = self.prefixes.abbreviate(iri.as_str()) {
+            if self.with_labels.get() {
+                self.used_prefixes.borrow_mut().insert(prefix.to_owned());
+            }
+            format!("{prefix}:{local}")
+        } else {
+            iri.to_string()
+        }
+    }
+
+    fn write_label(&self, node: &BlankNode) -> String {
+        if !self.with_labels.get() {
+            return "_:".to_owned();
+        }
+        let mut labels = self.labels.borrow_mut();
+        let next = labels.len();
+        labels
+            .entry(node.clone())
+            .or_insert_with(|| format!("_:b{next}"))
+            .clone()
+    }
+
+    fn write_literal(&self, literal: &Literal) -> String {
+        let value = literal.value();
+        let quoted = format!("\"{}\"", escape(value));
+        if let Some(language) = literal.language() {
+            return format!("{quoted}@{language}");
+        }
+        let datatype = literal.datatype();
+        if datatype == xsd::STRING {
+            return quoted;
+        }
+        let is_bare = if datatype == xsd::INTEGER {
+            is_integer(value)
+        } else if datatype == xsd::DECIMAL {
+            value
+                .split_once('.')
+                .is_some_and(|(int, frac)| is_integer(&format!("{int}0")) && is_digits(frac))
+        } else if datatype == xsd::BOOLEAN {
+            matches!(value, "true" | "false")
+        } else {
+            false
+        };
+        if is_bare {
+            value.to_owned()
+        } else {
+            format!("{quoted}^^{}", self.write_iri(datatype))
+        }
+    }
+
+    /**
+     * The members of the well-formed RDF list starting at `head`,
+     * if all its nodes can be nested.
+     */
+    fn list_members(&self, head: &BlankNode) -> Option<Vec<&Term>> {
+        let mut members = Vec::new();
+        let mut current = head;
+        loop {
+            if !self.nested.contains(current) {
+                return None;
+            }
+            let (mut first, mut rest) = (None, None);
+            for (predicate, object) in self.predicate_objects(current.as_ref().into()) {
+                if *predicate == rdf::FIRST && first.is_none() {
+                    first = Some(object);
+                } else if *predicate == rdf::REST && rest.is_none() {
+                    rest = Some(object);
+                } else {
+                    return None;
+                }
+            }
+            members.push(first?);
+            match rest? {
+                Term::NamedNode(nil) if *nil == rdf::NIL => return Some(members),
+                Term::BlankNode(next) => current = next,
+                Term::NamedNode(_) | Term::Literal(_) | Term::Triple(_) => return None,
+            }
+        }
+    }
+
+    fn write_term(&self, term: &Term, depth: usize) -> String {
+        match term {
+            Term::NamedNode(node) if *node == rdf::NIL => "()".to_owned(),
+            Term::NamedNode(node) => self.write_iri(node.as_ref()),
+            Term::Literal(literal) => self.write_literal(literal),
+            Term::BlankNode(node) if self.nested.contains(node) => {
+                if let Some(members) = self.list_members(node) {
+                    let members = members
+                        .into_iter()
+                        .map(|member| self.write_term(member, depth))
+                        .collect::<Vec<_>>();
+                    format!("( {} )", members.join(" "))
+                } else {
+                    self.write_nested(node.as_ref().into(), depth)
+                }
+            }
+            Term::BlankNode(node) => self.write_label(node),
+            Term::Triple(triple) => format!(
+                "<< {} {} {} >>",
+                self.write_term(&triple.subject.clone().into(), depth),
+                self.write_iri(triple.predicate.as_ref()),
+                self.write_term(&triple.object, depth)
+            ),
+        }
+    }
+
+    fn write_subject(&self, subject: SubjectRef) -> String {
+        match subject {
+            SubjectRef::NamedNode(node) => self.write_iri(node),
+            SubjectRef::BlankNode(node) => self.write_label(&node.into_owned()),
+            SubjectRef::Triple(triple) => self.write_term(&triple.clone().into(), 0),
+        }
+    }
+
+    /**
+     * Writes the predicates and objects of `subject`,
+     * each predicate on a line of its own,
+     * indented to `depth`.
+     */
+    fn write_predicate_objects(&self, subject: SubjectRef, depth: usize) -> String {
+        let mut by_predicate: BTreeMap<(usize, String), Vec<&Term>> = BTreeMap::new();
+        for (predicate, object) in self.predicate_objects(subject) {
+            let rank = predicate_rank(predicate);
+            let predicate = if *predicate == rdf::TYPE {
+                "a".to_owned()
+            } else {
+                self.write_iri(predicate.as_ref())
+            };
+            by_predicate
+                .entry((rank, predicate))
+                .or_default()
+                .push(object);
+        }
+        let separator = format!(" ;\n{}", INDENT.repeat(depth));
+        by_predicate
+            .into_iter()
+            .map(|((_, predicate), objects)| {
+                let mut objects = self
+                    .sort_objects(objects)
+                    .into_iter()
+                    .map(|object| self.write_term(object, depth))
+                    .collect::<Vec<_>>();
+                objects.dedup();
+                format!("{predicate} {}", objects.join(" , "))
+            })
+            .collect::<Vec<_>>()
+            .join(&separator)
+    }
+
+    fn write_nested(&self, subject: SubjectRef, depth: usize) -> String {
+        let predicate_objects = self.predicate_objects(subject);
+        if predicate_objects.is_empty() {
+            return "[]".to_owned();
+        }
+        let inner = self.write_predicate_objects(subject, depth + 1);
+        if predicate_objects.len() == 1 && !inner.contains('\n') {
+            format!("[ {inner} ]")
+        } else {
+            format!(
+                "[\n{}{inner}\n{}]",
+                INDENT.repeat(depth + 1),
+                INDENT.repeat(depth)
+            )
+        }
+    }
+
+    fn write_block(&self, subject: SubjectRef) -> String {
+        let subject_str = match subject {
+            SubjectRef::BlankNode(node) if self.labels_needed(node.into_owned()) => {
+                self.write_subject(subject)
+            }
+            SubjectRef::BlankNode(_) => {
+                // Not referenced at all
+                return format!("{} .\n", self.write_nested(subject, 0));
+            }
+            SubjectRef::NamedNode(_) | SubjectRef::Triple(_) => self.write_subject(subject),
+        };
+        format!(
+            "{subject_str} {} .\n",
+            self.write_predicate_objects(subject, 1)
+        )
+    }
+
+    /**
+     * Whether the blank node `node` is referenced from anywhere,
+     * and thus needs a label when not nested.
+     */
+    fn labels_needed(&self, node: BlankNode) -> bool {
+        let node = Term::from(node);
+        self.about
+            .values()
+            .any(|predicate_objects| predicate_objects.iter().any(|(_, object)| *object == node))
+    }
+
+    /**
+     * The subjects to write at the top level, in order:
+     * Each node shape is followed by the named property shapes only it uses,
+     * then come the remaining property shapes, and then everything else.
+     */
+    fn top_level_subjects(&self) -> Vec<Subject> {
+        let mut keyed = self
+            .about
+            .keys()
+            .filter(|subject| match subject {
+                Subject::BlankNode(node) => !self.nested.contains(node),
+                Subject::NamedNode(_) | Subject::Triple(_) => true,
+            })
+            .map(|subject| {
+                let rank = if self.has_type(subject, sh::NODE_SHAPE) {
+                    0
+                } else if self.has_type(subject, sh::PROPERTY_SHAPE) {
+                    1
+                } else {
+                    2
+                };
+                let key = match subject {
+                    Subject::NamedNode(node) => (false, node.as_str().to_owned(), String::new()),
+                    Subject::BlankNode(_) | Subject::Triple(_) => (
+                        true,
+                        self.without_labels(|| self.write_block(subject.as_ref())),
+                        // The canonical blank nodes break ties
+                        subject.to_string(),
+                    ),
+                };
+                ((rank, key), subject.clone())
+            })
+            .collect::<Vec<_>>();
+        keyed.sort_by(|(key_a, _), (key_b, _)| key_a.cmp(key_b));
+
+        let mut users: HashMap<&Subject, usize> = HashMap::new();
+        for predicate_objects in self.about.values() {
+            for (_, object) in predicate_objects {
+                if let Term::NamedNode(node) = object {
+                    if let Some((subject, _)) =
+                        self.about.get_key_value(&Subject::NamedNode(node.clone()))
+                    {
+                        *users.entry(subject).or_default() += 1;
+                    }
+                }
+            }
+        }
+        let mut ordered = Vec::new();
+        let mut done = HashSet::new();
+        for ((rank, _), subject) in &keyed {
+            if *rank != 0 || !done.insert(subject.clone()) {
+                continue;
+            }
+            ordered.push(subject.clone());
+            let mut own_shapes = self
+                .predicate_objects(subject.as_ref())
+                .iter()
+                .filter_map(|(predicate, object)| match object {
+                    Term::NamedNode(shape) if *predicate == sh::PROPERTY => {
+                        Some(Subject::NamedNode(shape.clone()))
+                    }
+                    Term::NamedNode(_)
+                    | Term::BlankNode(_)
+                    | Term::Literal(_)
+                    | Term::Triple(_) => None,
+                })
+                .filter(|shape| users.get(shape) == Some(&1) && self.about.contains_key(shape))
+                .collect::<Vec<_>>();
+            own_shapes.sort_by_key(ToString::to_string);
+            for shape in own_shapes {
+                if done.insert(shape.clone()) {
+                    ordered.push(shape);
+                }
+            }
+        }
+        for (_, subject) in keyed {
+            if done.insert(subject.clone()) {
+                ordered.push(subject);
+            }
+        }
+        ordered
+    }
+
+    fn has_type(&self, subject: &Subject, typ: NamedNodeRef) -> bool {
+        self.predicate_objects(subject.as_ref())
+            .iter()
+            .any(|(predicate, object)| {
+                *predicate == rdf::TYPE && *object == typ.into_owned().into()
+            })
+    }
+
+    /**
+     * Writes Turtle, or `TriG` if `graph` is given,
+     * with all the triples within that named graph.
+     */
+    fn write_turtle(&self, writer: &mut impl Write, graph: Option<NamedNodeRef>) -> io::Result<()> {
+        let blocks = self
+            .top_level_subjects()
+            .iter()
+            .map(|subject| self.write_block(subject.as_ref()))
+            .collect::<Vec<_>>();
+        let graph = graph.map(|graph| self.write_iri(graph));
+        let used_prefixes = self.used_prefixes.borrow();
+        let declarations = self
+            .prefixes
+            .iter()
+            .filter(|(prefix, _)| used_prefixes.contains(*prefix))
+            .collect::<Vec<_>>();
+        let width = declarations
+            .iter()
+            .map(|(prefix, _)| prefix.len())
+            .max()
+            .unwrap_or_default();
+        for (prefix, namespace) in &declarations {
+            let prefix = format!("{prefix}:");
+            writeln!(
+                writer,
+                "@prefix {prefix:width$} <{namespace}> .",
+                width = width + 1
+            )?;
+        }
+        if let Some(graph) = graph {
+            writeln!(writer)?;
+            write!(writer, "{graph} {{")?;
+            for block in blocks {
+                writeln!(writer)?;
+                for line in block.lines() {
+                    writeln!(writer, "{INDENT}{line}")?;
+                }
+            }
+            writeln!(writer, "}}")?;
+        } else {
+            for block in blocks {
+                writeln!(writer)?;
+                write!(writer, "{block}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Whether following the (single) parents of the blank node `node`
+ * leads back to `node`, in which case it can not be nested.
+ */
+fn is_in_cycle(parents: &HashMap<BlankNode, Vec<Subject>>, node: &BlankNode) -> bool {
+    let mut visited = HashSet::new();
+    let mut current = node;
+    loop {
+        let Some([Subject::BlankNode(parent)]) = parents.get(current).map(Vec::as_slice) else {
+            return false;
+        };
+        if parent == node {
+            return true;
+        }
+        if !visited.insert(parent) {
+            return false;
+        }
+        current = parent;
+    }
+}
+
+/**
+ * Whether the blank node `node` is a shape with a target,
+ * which gets written on its own, like the named shapes.
+ */
+fn is_target_shape(about: &HashMap<Subject, Vec<(NamedNode, Term)>>, node: &BlankNode) -> bool {
+    about
+        .get(&Subject::BlankNode(node.clone()))
+        .is_some_and(|predicate_objects| {
+            predicate_objects
+                .iter()
+                .any(|(predicate, _)| TARGET_PREDICATES.contains(&predicate.as_ref()))
+        })
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|chr| chr.is_ascii_digit())
+}
+
+fn is_integer(value: &str) -> bool {
+    is_digits(value.strip_prefix(['+', '-']).unwrap_or(value))
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for chr in value.chars() {
+        match chr {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            _ => escaped.push(chr),
+        }
+    }
+    escaped
+}
+
+/**
+ * `prefixes`, plus `sh:`, if not yet declared.
+ */
+fn with_shacl_prefix(prefixes: &Prefixes) -> Prefixes {
+    let mut prefixes = prefixes.clone();
+    if !prefixes.has_namespace(sh::NS_BASE) {
+        prefixes.insert("sh", sh::NS_BASE);
+    }
+    prefixes
+}
+
+/**
+ * Writes the triples of the graph `graph` of `store` as Turtle,
+ * using `prefixes` (plus `sh:`, if not yet declared)
+ * to abbreviate the IRIs.
+ * Only the prefixes actually used get declared.
+ *
+ * # Errors
+ *
+ * If reading from the store or writing fails.
+ */
+pub fn write_turtle(
+    store: &Store,
+    graph: GraphNameRef,
+    prefixes: &Prefixes,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    PrettyWriter::new(store, graph, &with_shacl_prefix(prefixes))?.write_turtle(writer, None)
+}
+
+/**
+ * Prefixes the label of the blank node `node`,
+ * so it is also a valid XML name, as required by RDF/XML.
+ */
+fn relabel(node: &BlankNode) -> BlankNode {
+    BlankNode::new_unchecked(format!("b{}", node.as_str()))
+}
+
+/**
+ * The triples of the graph `graph` of `store`,
+ * with canonical blank node labels,
+ * which do not depend on the order the triples were inserted in.
+ */
+fn canonical_graph(store: &Store, graph: GraphNameRef) -> Result<Graph, StorageError> {
+    let mut canonical = Graph::new();
+    for quad in store.quads_for_pattern(None, None, None, Some(graph)) {
+        canonical.insert(quad?.as_ref());
+    }
+    canonical.canonicalize();
+    Ok(canonical)
+}
+
+/**
+ * The triples of the default graph of `store`,
+ * with canonical blank node labels, sorted,
+ * so they get written the same across runs.
+ */
+fn canonical_triples(store: &Store) -> io::Result<Vec<Triple>> {
+    let mut triples = canonical_graph(store, GraphNameRef::DefaultGraph)?
+        .iter()
+        .map(|triple| {
+            let mut triple = triple.into_owned();
+            if let Subject::BlankNode(node) = &triple.subject {
+                triple.subject = relabel(node).into();
+            }
+            if let Term::BlankNode(node) = &triple.object {
+                triple.object = relabel(node).into();
+            }
+            triple
+        })
+        .collect::<Vec<_>>();
+    triples.sort_by_key(ToString::to_string);
+    Ok(triples)
+}
+
+/**
+ * Writes the triples of the default graph of `store`
+ * as N-Triples, or as N-Quads if `graph` is given,
+ * with all the triples within that named graph.
+ */
+fn write_lines(
+    store: &Store,
+    graph: Option<NamedNodeRef>,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    for triple in canonical_triples(store)? {
+        match graph {
+            Some(graph) => writeln!(writer, "{triple} {graph} .")?,
+            None => writeln!(writer, "{triple} .")?,
+        }
+    }
+    Ok(())
+}
+
+fn write_rdf_xml(store: &Store, writer: &mut impl Write) -> io::Result<()> {
+    let mut triple_writer =
+        GraphSerializer::from_format(GraphFormat::RdfXml).triple_writer(writer)?;
+    for triple in canonical_triples(store)? {
+        triple_writer.write(&triple)?;
+    }
+    triple_writer.finish()
+}
+
+/**
+ * Writes the shapes in `store` (in its default graph) in the format `format`,
+ * using `prefixes` (plus `sh:`) where the format supports them.
+ * The dataset formats (N-Quads, `TriG` and JSON-LD)
+ * put the shapes into the named graph `shapes_graph`, if given;
+ * N-Quads and `TriG` fall back to the default graph, with a warning.
+ *
+ * # Errors
+ *
+ * If reading from the store or writing fails.
+ */
+pub fn write(
+    store: &Store,
+    format: OutputFormat,
+    prefixes: &Prefixes,
+    shapes_graph: Option<NamedNodeRef>,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    if shapes_graph.is_none() && matches!(format, OutputFormat::NQuads | OutputFormat::TriG) {
+        log::warn!("No shapes graph given, so the shapes get written into the default graph");
+    }
+    let prefixes = with_shacl_prefix(prefixes);
+    let graph = GraphNameRef::DefaultGraph;
+    match format {
+        OutputFormat::Turtle => {
+            PrettyWriter::new(store, graph, &prefixes)?.write_turtle(writer, None)
+        }
+        OutputFormat::TriG => {
+            PrettyWriter::new(store, graph, &prefixes)?.write_turtle(writer, shapes_graph)
+        }
+        OutputFormat::NTriples => write_lines(store, None, writer),
+        OutputFormat::NQuads => write_lines(store, shapes_graph, writer),
+        OutputFormat::RdfXml => write_rdf_xml(store, writer),
+        OutputFormat::JsonLd => {
+            let prefixes = prefixes.for_json_ld();
+            PrettyWriter::new(store, graph, &prefixes)?.write_json_ld(writer, shapes_graph)
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Writes JSON-LD, with a compact context made of the used prefixes.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use oxigraph::model::vocab::{rdf, xsd};
+use oxigraph::model::{Literal, NamedNodeRef, Subject, SubjectRef, Term};
+
+use super::{is_digits, predicate_rank, PrettyWriter};
+
+const JSON_INDENT: &str = "  ";
+/// The maximum length of the members of an object or array written on a single line
+const MAX_INLINE_LEN: usize = 60;
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for chr in value.chars() {
+        match chr {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            _ if chr.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", u32::from(chr));
+            }
+            _ => escaped.push(chr),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/**
+ * Whether `value` (an `xsd:integer`) can be written as a JSON number as is.
+ */
+fn is_json_integer(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    is_digits(digits) && (digits == "0" || !digits.starts_with('0'))
+}
+
+/**
+ * Writes a JSON object or array with the given (already written) members,
+ * on a single line if they are all single-line and short enough,
+ * and indented to `depth` otherwise.
+ */
+fn json_container(open: char, close: char, members: &[String], depth: usize) -> String {
+    if members.is_empty() {
+        format!("{open}{close}")
+    } else if members.iter().all(|member| !member.contains('\n'))
+        && members.iter().map(String::len).sum::<usize>() <= MAX_INLINE_LEN
+    {
+        format!("{open} {} {close}", members.join(", "))
+    } else {
+        let indent = JSON_INDENT.repeat(depth + 1);
+        format!(
+            "{open}\n{indent}{}\n{}{close}",
+            members.join(&format!(",\n{indent}")),
+            JSON_INDENT.repeat(depth)
+        )
+    }
+}
+
+impl PrettyWriter<'_> {
+    fn json_iri(&self, iri: NamedNodeRef) -> String {
+        let iri = self.write_iri(iri);
+        // Non-abbreviated IRIs are written as `<iri>` by `write_iri`
+        json_string(
+            iri.strip_prefix('<')
+                .and_then(|iri| iri.strip_suffix('>'))
+                .unwrap_or(&iri),
+        )
+    }
+
+    fn json_literal(&self, literal: &Literal, depth: usize) -> String {
+        let value = literal.value();
+        if let Some(language) = literal.language() {
+            return json_container(
+                '{',
+                '}',
+                &[
+                    format!("\"@value\": {}", json_string(value)),
+                    format!("\"@language\": {}", json_string(language)),
+                ],
+                depth,
+            );
+        }
+        let datatype = literal.datatype();
+        if datatype == xsd::STRING {
+            json_string(value)
+        } else if (datatype == xsd::INTEGER && is_json_integer(value))
+            || (datatype == xsd::BOOLEAN && matches!(value, "true" | "false"))
+        {
+            value.to_owned()
+        } else {
+            json_container(
+                '{',
+                '}',
+                &[
+                    format!("\"@value\": {}", json_string(value)),
+                    format!("\"@type\": {}", self.json_iri(datatype)),
+                ],
+                depth,
+            )
+        }
+    }
+
+    fn json_value(&self, term: &Term, depth: usize) -> String {
+        let id = |id: String| json_container('{', '}', &[format!("\"@id\": {id}")], depth);
+        match term {
+            Term::NamedNode(node) if *node == rdf::NIL => {
+                json_container('{', '}', &["\"@list\": []".to_owned()], depth)
+            }
+            Term::NamedNode(node) => id(self.json_iri(node.as_ref())),
+            Term::Literal(literal) => self.json_literal(literal, depth),
+            Term::BlankNode(node) if self.nested.contains(node) => {
+                if let Some(members) = self.list_members(node) {
+                    let members = members
+                        .into_iter()
+                        .map(|member| self.json_value(member, depth + 2))
+                        .collect::<Vec<_>>();
+                    let list = json_container('[', ']', &members, depth + 1);
+                    json_container('{', '}', &[format!("\"@list\": {list}")], depth)
+                } else {
+                    self.json_node(node.as_ref().into(), depth, false)
+                }
+            }
+            Term::BlankNode(node) => id(json_string(&self.write_label(node))),
+            // JSON-LD has no quoted triples, so they get written as (N-Triples) strings
+            Term::Triple(triple) => json_string(&triple.to_string()),
+        }
+    }
+
+    /**
+     * Writes the node object of `subject`,
+     * including its `@id` if `with_id`.
+     */
+    fn json_node(&self, subject: SubjectRef, depth: usize, with_id: bool) -> String {
+        let mut members = Vec::new();
+        if with_id {
+            let id = match subject {
+                SubjectRef::NamedNode(node) => self.json_iri(node),
+                SubjectRef::BlankNode(node) => json_string(&self.write_label(&node.into_owned())),
+                SubjectRef::Triple(triple) => json_string(&triple.to_string()),
+            };
+            members.push(format!("\"@id\": {id}"));
+        }
+        let mut types = Vec::new();
+        let mut by_predicate: BTreeMap<(usize, String), Vec<&Term>> = BTreeMap::new();
+        for (predicate, object) in self.predicate_objects(subject) {
+            match object {
+                Term::NamedNode(typ) if *predicate == rdf::TYPE => {
+                    types.push(self.json_iri(typ.as_ref()));
+                }
+                Term::NamedNode(_) | Term::BlankNode(_) | Term::Literal(_) | Term::Triple(_) => {
+                    by_predicate
+                        .entry((predicate_rank(predicate), self.json_iri(predicate.as_ref())))
+                        .or_default()
+                        .push(object);
+                }
+            }
+        }
+        types.sort();
+        types.dedup();
+        match types.as_slice() {
+            [] => {}
+            [typ] => members.push(format!("\"@type\": {typ}")),
+            _ => members.push(format!(
+                "\"@type\": {}",
+                json_container('[', ']', &types, depth + 1)
+            )),
+        }
+        for ((_, predicate), objects) in by_predicate {
+            let value = if let [object] = objects.as_slice() {
+                self.json_value(object, depth + 1)
+            } else {
+                let values = self
+                    .sort_objects(objects)
+                    .into_iter()
+                    .map(|object| self.json_value(object, depth + 2))
+                    .collect::<Vec<_>>();
+                json_container('[', ']', &values, depth + 1)
+            };
+            members.push(format!("{predicate}: {value}"));
+        }
+        json_container('{', '}', &members, depth)
+    }
+
+    /**
+     * Writes JSON-LD, with all the triples within the named graph `graph`,
+     * if given.
+     */
+    pub(super) fn write_json_ld(
+        &self,
+        writer: &mut impl Write,
+        graph: Option<NamedNodeRef>,
+    ) -> io::Result<()> {
+        let nodes = self
+            .top_level_subjects()
+            .iter()
+            .map(|subject| {
+                let with_id = match subject {
+                    Subject::BlankNode(node) => self.labels_needed(node.clone()),
+                    Subject::NamedNode(_) | Subject::Triple(_) => true,
+                };
+                self.json_node(subject.as_ref(), 2, with_id)
+            })
+            .collect::<Vec<_>>();
+        let graph = graph.map(|graph| self.json_iri(graph));
+        let used_prefixes = self.used_prefixes.borrow();
+        let context = self
+            .prefixes
+            .iter()
+            .filter(|(prefix, _)| used_prefixes.contains(*prefix))
+            .map(|(prefix, namespace)| {
+                format!("{}: {}", json_string(prefix), json_string(namespace))
+            })
+            .collect::<Vec<_>>();
+        let mut members = vec![format!(
+            "\"@context\": {}",
+            json_container('{', '}', &context, 1)
+        )];
+        if let Some(graph) = graph {
+            members.push(format!("\"@id\": {graph}"));
+        }
+        members.push(format!(
+            "\"@graph\": {}",
+            json_container('[', ']', &nodes, 1)
+        ));
+        writeln!(writer, "{}", json_container('{', '}', &members, 0))
+    }
+}
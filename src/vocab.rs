@@ -27,6 +27,7 @@ pub mod basics {
     use oxigraph::model::{vocab::xsd, LiteralRef, TermRef};
 
     pub const NS_BASE_RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+    pub const NS_BASE_XSD: &str = "http://www.w3.org/2001/XMLSchema#";
 
     typed_literal!(BOOL_FALSE, "false", xsd::BOOLEAN);
     typed_literal!(BOOL_TRUE, "true", xsd::BOOLEAN);
@@ -49,6 +50,12 @@ pub mod sh {
         "closed",
         "If set to true then the shape is closed."
     );
+    named_node!(
+        IGNORED_PROPERTIES,
+        NS_BASE,
+        "ignoredProperties",
+        "An optional RDF list of properties that are also permitted in addition to those explicitly enumerated via sh:property/sh:path."
+    );
     named_node!(
         PROPERTY,
         NS_BASE,
@@ -91,15 +98,171 @@ pub mod sh {
         "node",
         "Specifies the node shape that all value nodes must conform to."
     );
+    named_node!(
+        HAS_VALUE,
+        NS_BASE,
+        "hasValue",
+        "Specifies a value that must be among the value nodes."
+    );
+    named_node!(
+        QUALIFIED_VALUE_SHAPE,
+        NS_BASE,
+        "qualifiedValueShape",
+        "The shape that a specified number of values must conform to."
+    );
+    named_node!(
+        QUALIFIED_MIN_COUNT,
+        NS_BASE,
+        "qualifiedMinCount",
+        "The minimum number of value nodes that conform to the shape."
+    );
+    named_node!(
+        QUALIFIED_MAX_COUNT,
+        NS_BASE,
+        "qualifiedMaxCount",
+        "The maximum number of value nodes that can conform to the shape."
+    );
+    named_node!(
+        QUALIFIED_VALUE_SHAPES_DISJOINT,
+        NS_BASE,
+        "qualifiedValueShapesDisjoint",
+        "Can be used to mark the qualified value shape to be disjoint with its sibling shapes."
+    );
     named_node!(PATTERN, NS_BASE, "pattern", "Specifies a regular expression pattern that the string representations of the value nodes must match.");
+    named_node!(
+        ALTERNATIVE_PATH,
+        NS_BASE,
+        "alternativePath",
+        "The (single) value of this property must be a list of path elements, representing the elements of alternative paths."
+    );
+    named_node!(
+        INVERSE_PATH,
+        NS_BASE,
+        "inversePath",
+        "The (single) value of this property represents an inverse path (object to subject)."
+    );
+    named_node!(
+        MIN_INCLUSIVE,
+        NS_BASE,
+        "minInclusive",
+        "Specifies the minimum value (inclusive) that each value node must have."
+    );
+    named_node!(
+        MAX_INCLUSIVE,
+        NS_BASE,
+        "maxInclusive",
+        "Specifies the maximum value (inclusive) that each value node must have."
+    );
+    named_node!(
+        MIN_EXCLUSIVE,
+        NS_BASE,
+        "minExclusive",
+        "Specifies the minimum value (exclusive) that each value node must have."
+    );
+    named_node!(
+        MAX_EXCLUSIVE,
+        NS_BASE,
+        "maxExclusive",
+        "Specifies the maximum value (exclusive) that each value node must have."
+    );
+    named_node!(
+        MIN_LENGTH,
+        NS_BASE,
+        "minLength",
+        "Specifies the minimum string length of each value node."
+    );
+    named_node!(
+        MAX_LENGTH,
+        NS_BASE,
+        "maxLength",
+        "Specifies the maximum string length of each value node."
+    );
+    named_node!(
+        FLAGS,
+        NS_BASE,
+        "flags",
+        "An optional flag to be used with regular expression pattern matching."
+    );
+    named_node!(
+        AND,
+        NS_BASE,
+        "and",
+        "RDF list of shapes to validate the value nodes against."
+    );
+    named_node!(
+        OR,
+        NS_BASE,
+        "or",
+        "Specifies a list of shapes so that the value nodes must conform to at least one of the shapes."
+    );
+    named_node!(
+        NOT,
+        NS_BASE,
+        "not",
+        "Specifies a shape that the value nodes must not conform to."
+    );
+    named_node!(
+        IN,
+        NS_BASE,
+        "in",
+        "Specifies a list of allowed values so that each value node must be among the members of the given list."
+    );
     named_node!(
         NODE_KIND,
         NS_BASE,
         "nodeKind",
         "Specifies the node kind (e.g. IRI or literal) each value node."
     );
+    named_node!(
+        LITERAL,
+        NS_BASE,
+        "Literal",
+        "The node kind of all literals."
+    );
     named_node!(TARGET_OBJECTS_OF, NS_BASE, "targetObjectsOf", "Links a shape to a property, indicating that all all objects of triples that have the given property as their predicate must conform to the shape.");
     named_node!(TARGET_SUBJECTS_OF, NS_BASE, "targetSubjectsOf", "Links a shape to a property, indicating that all subjects of triples that have the given property as their predicate must conform to the shape.");
+    named_node!(
+        XONE,
+        NS_BASE,
+        "xone",
+        "Specifies a list of shapes so that the value nodes must conform to exactly one of the shapes."
+    );
+    named_node!(
+        EQUALS,
+        NS_BASE,
+        "equals",
+        "Specifies a property that must have the same values as the value nodes."
+    );
+    named_node!(
+        DISJOINT,
+        NS_BASE,
+        "disjoint",
+        "Specifies a property where the set of values must be disjoint with the value nodes."
+    );
+    named_node!(
+        SPARQL_CONSTRAINT,
+        NS_BASE,
+        "SPARQLConstraint",
+        "The class of constraints based on SPARQL SELECT queries."
+    );
+    named_node!(
+        SPARQL,
+        NS_BASE,
+        "sparql",
+        "Links a shape with SPARQL constraints."
+    );
+    named_node!(
+        SELECT,
+        NS_BASE,
+        "select",
+        "The SPARQL SELECT query to execute."
+    );
+    named_node!(
+        MESSAGE,
+        NS_BASE,
+        "message",
+        "A human-readable message (possibly with placeholders for variables) explaining the cause of the result."
+    );
     named_node!(
         NAME,
         NS_BASE,
@@ -114,6 +277,61 @@ pub mod sh {
     );
 }
 
+pub mod xsd {
+    //! [XML Schema Definition](http://www.w3.org/2001/XMLSchema) facets,
+    //! as used in OWL 2 datatype restrictions;
+    //! the datatypes themselves are in [`oxigraph::model::vocab::xsd`].
+    use const_format::concatcp;
+    use oxigraph::model::NamedNodeRef;
+
+    pub const NS_BASE: &str = super::basics::NS_BASE_XSD;
+    pub const NS_PREFFERED_PREFIX: &str = "xsd";
+
+    named_node!(
+        MIN_INCLUSIVE,
+        NS_BASE,
+        "minInclusive",
+        "The inclusive lower bound of the value space."
+    );
+    named_node!(
+        MAX_INCLUSIVE,
+        NS_BASE,
+        "maxInclusive",
+        "The inclusive upper bound of the value space."
+    );
+    named_node!(
+        MIN_EXCLUSIVE,
+        NS_BASE,
+        "minExclusive",
+        "The exclusive lower bound of the value space."
+    );
+    named_node!(
+        MAX_EXCLUSIVE,
+        NS_BASE,
+        "maxExclusive",
+        "The exclusive upper bound of the value space."
+    );
+    named_node!(LENGTH, NS_BASE, "length", "The exact length of values.");
+    named_node!(
+        MIN_LENGTH,
+        NS_BASE,
+        "minLength",
+        "The minimum length of values."
+    );
+    named_node!(
+        MAX_LENGTH,
+        NS_BASE,
+        "maxLength",
+        "The maximum length of values."
+    );
+    named_node!(
+        PATTERN,
+        NS_BASE,
+        "pattern",
+        "A regular expression that the lexical forms of values have to match."
+    );
+}
+
 pub mod owl {
     //! [Web Ontology Language(OWL)](http://www.w3.org/2002/07/owl) vocabulary.
     use const_format::concatcp;
@@ -123,4 +341,104 @@ pub mod owl {
     pub const NS_PREFFERED_PREFIX: &str = "owl";
 
     named_node!(DATATYPE_PROPERTY, NS_BASE, "DatatypeProperty", "TODO"); // TODO Fill in description
+    named_node!(THING, NS_BASE, "Thing", "The class of OWL individuals.");
+    named_node!(EQUIVALENT_CLASS, NS_BASE, "equivalentClass", "The property that determines that two given classes are equivalent, and that is used to specify datatype definitions.");
+    named_node!(
+        ON_DATATYPE,
+        NS_BASE,
+        "onDatatype",
+        "The property that determines the datatype that a datatype restriction refers to."
+    );
+    named_node!(WITH_RESTRICTIONS, NS_BASE, "withRestrictions", "The property that determines the collection of facet-value pairs that define a datatype restriction.");
+    named_node!(INTERSECTION_OF, NS_BASE, "intersectionOf", "The property that determines the collection of classes or data ranges that build an intersection.");
+    named_node!(
+        UNION_OF,
+        NS_BASE,
+        "unionOf",
+        "The property that determines the collection of classes or data ranges that build a union."
+    );
+    named_node!(
+        COMPLEMENT_OF,
+        NS_BASE,
+        "complementOf",
+        "The property that determines that a given class is the complement of another class."
+    );
+    named_node!(ONE_OF, NS_BASE, "oneOf", "The property that determines the collection of individuals or data values that build an enumeration.");
+    named_node!(
+        FUNCTIONAL_PROPERTY,
+        NS_BASE,
+        "FunctionalProperty",
+        "The class of functional properties."
+    );
+    named_node!(
+        INVERSE_FUNCTIONAL_PROPERTY,
+        NS_BASE,
+        "InverseFunctionalProperty",
+        "The class of inverse-functional properties."
+    );
+    named_node!(
+        RESTRICTION,
+        NS_BASE,
+        "Restriction",
+        "The class of property restrictions."
+    );
+    named_node!(
+        DISJOINT_WITH,
+        NS_BASE,
+        "disjointWith",
+        "The property that determines that two given classes are disjoint."
+    );
+    named_node!(
+        ALL_DISJOINT_CLASSES,
+        NS_BASE,
+        "AllDisjointClasses",
+        "The class of collections of pairwise disjoint classes."
+    );
+    named_node!(
+        MEMBERS,
+        NS_BASE,
+        "members",
+        "The property that determines the collection of members in either a owl:AllDifferent, owl:AllDisjointClasses or owl:AllDisjointProperties axiom."
+    );
+    named_node!(
+        EQUIVALENT_PROPERTY,
+        NS_BASE,
+        "equivalentProperty",
+        "The property that determines that two given properties are equivalent."
+    );
+    named_node!(
+        PROPERTY_DISJOINT_WITH,
+        NS_BASE,
+        "propertyDisjointWith",
+        "The property that determines that two given properties are disjoint."
+    );
+    named_node!(
+        SYMMETRIC_PROPERTY,
+        NS_BASE,
+        "SymmetricProperty",
+        "The class of symmetric properties."
+    );
+    named_node!(
+        ASYMMETRIC_PROPERTY,
+        NS_BASE,
+        "AsymmetricProperty",
+        "The class of asymmetric properties."
+    );
+    named_node!(
+        IRREFLEXIVE_PROPERTY,
+        NS_BASE,
+        "IrreflexiveProperty",
+        "The class of irreflexive properties."
+    );
+    named_node!(
+        TRANSITIVE_PROPERTY,
+        NS_BASE,
+        "TransitiveProperty",
+        "The class of transitive properties."
+    );
+    named_node!(ON_CLASS, NS_BASE, "onClass", "The property that determines the class that a qualified object cardinality restriction refers to.");
+    named_node!(ON_DATA_RANGE, NS_BASE, "onDataRange", "The property that determines the data range that a qualified data cardinality restriction refers to.");
+    named_node!(QUALIFIED_CARDINALITY, NS_BASE, "qualifiedCardinality", "The property that determines the cardinality of an exact qualified cardinality restriction.");
+    named_node!(MIN_QUALIFIED_CARDINALITY, NS_BASE, "minQualifiedCardinality", "The property that determines the cardinality of a minimum qualified cardinality restriction.");
+    named_node!(MAX_QUALIFIED_CARDINALITY, NS_BASE, "maxQualifiedCardinality", "The property that determines the cardinality of a maximum qualified cardinality restriction.");
 }
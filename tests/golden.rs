@@ -0,0 +1,513 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Golden tests:
+//! Each directory under `tests/golden/` contains an `input.*` ontology
+//! (usually `input.ttl`, but any of the supported input formats),
+//! the `expected.ttl` SHACL shapes,
+//! and optionally an `args` file with additional CLI arguments,
+//! one per line (lines starting with `#` are ignored).
+//! The generated shapes are compared to the expected ones
+//! as graphs (modulo blank node labels), not textually.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use json_event_parser::{JsonEvent, JsonReader};
+use oxigraph::io::{DatasetFormat, DatasetParser, GraphFormat, GraphParser};
+use oxigraph::model::vocab::{rdf, xsd};
+use oxigraph::model::{
+    BlankNode, Graph, GraphNameRef, Literal, NamedNode, NamedNodeRef, Subject, Term, TripleRef,
+};
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+}
+
+fn read_args(case_dir: &Path) -> Vec<String> {
+    fs::read_to_string(case_dir.join("args"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn input_file(case_dir: &Path) -> PathBuf {
+    fs::read_dir(case_dir)
+        .expect("Failed to list golden test case files")
+        .map(|entry| entry.expect("Failed to read golden test case file").path())
+        .find(|path| path.file_stem().is_some_and(|stem| stem == "input"))
+        .expect("Golden test case without an input file")
+}
+
+fn run(case_dir: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_owl2shacl"))
+        .args(read_args(case_dir))
+        .arg(input_file(case_dir))
+        .env("RUST_LOG", "error")
+        .output()
+        .expect("Failed to run owl2shacl")
+}
+
+fn parse_graph(format: GraphFormat, data: &[u8]) -> Graph {
+    let mut graph = Graph::new();
+    for triple in GraphParser::from_format(format)
+        .read_triples(BufReader::new(data))
+        .expect("Failed to create parser")
+    {
+        graph.insert(&triple.expect("Failed to parse"));
+    }
+    graph.canonicalize();
+    graph
+}
+
+fn parse_turtle(turtle: &[u8]) -> Graph {
+    parse_graph(GraphFormat::Turtle, turtle)
+}
+
+/**
+ * Parses the triples of the named graph `graph_name` in `data`.
+ */
+fn parse_named_graph(format: DatasetFormat, data: &[u8], graph_name: NamedNodeRef) -> Graph {
+    let mut graph = Graph::new();
+    for quad in DatasetParser::from_format(format)
+        .read_quads(BufReader::new(data))
+        .expect("Failed to create parser")
+    {
+        let quad = quad.expect("Failed to parse");
+        assert_eq!(
+            quad.graph_name.as_ref(),
+            GraphNameRef::NamedNode(graph_name)
+        );
+        graph.insert(quad.as_ref());
+    }
+    graph.canonicalize();
+    graph
+}
+
+enum Json {
+    String(String),
+    Number(String),
+    Boolean(bool),
+    Array(Vec<Self>),
+    Object(BTreeMap<String, Self>),
+}
+
+fn read_json_value(reader: &mut JsonReader<&[u8]>, buffer: &mut Vec<u8>) -> Json {
+    let event = reader.read_event(buffer).expect("Invalid JSON");
+    read_json_event(reader, Start::of(event))
+}
+
+/**
+ * An owned version of the events starting a JSON value.
+ */
+enum Start {
+    Value(Json),
+    Array,
+    Object,
+}
+
+impl Start {
+    fn of(event: JsonEvent) -> Self {
+        match event {
+            JsonEvent::String(value) => Self::Value(Json::String(value.to_owned())),
+            JsonEvent::Number(value) => Self::Value(Json::Number(value.to_owned())),
+            JsonEvent::Boolean(value) => Self::Value(Json::Boolean(value)),
+            JsonEvent::StartArray => Self::Array,
+            JsonEvent::StartObject => Self::Object,
+            JsonEvent::Null
+            | JsonEvent::EndArray
+            | JsonEvent::EndObject
+            | JsonEvent::ObjectKey(_)
+            | JsonEvent::Eof => panic!("Unexpected JSON event {event:?}"),
+        }
+    }
+}
+
+fn read_json_event(reader: &mut JsonReader<&[u8]>, start: Start) -> Json {
+    let mut buffer = Vec::new();
+    match start {
+        Start::Value(value) => value,
+        Start::Array => {
+            let mut members = Vec::new();
+            loop {
+                let event = reader.read_event(&mut buffer).expect("Invalid JSON");
+                if event == JsonEvent::EndArray {
+                    return Json::Array(members);
+                }
+                let start = Start::of(event);
+                members.push(read_json_event(reader, start));
+            }
+        }
+        Start::Object => {
+            let mut members = BTreeMap::new();
+            loop {
+                let key = match reader.read_event(&mut buffer).expect("Invalid JSON") {
+                    JsonEvent::EndObject => return Json::Object(members),
+                    JsonEvent::ObjectKey(key) => key.to_owned(),
+                    event @ (JsonEvent::String(_)
+                    | JsonEvent::Number(_)
+                    | JsonEvent::Boolean(_)
+                    | JsonEvent::Null
+                    | JsonEvent::StartArray
+                    | JsonEvent::EndArray
+                    | JsonEvent::StartObject
+                    | JsonEvent::Eof) => panic!("Unexpected JSON event {event:?}"),
+                };
+                members.insert(key, read_json_value(reader, &mut buffer));
+            }
+        }
+    }
+}
+
+/**
+ * Converts the JSON-LD written by us - compacted with prefixes only -
+ * back into a graph.
+ */
+struct JsonLdReader {
+    context: BTreeMap<String, String>,
+    blank_nodes: HashMap<String, BlankNode>,
+    graph: Graph,
+}
+
+impl JsonLdReader {
+    fn iri(&self, compact: &str) -> NamedNode {
+        let iri = compact
+            .split_once(':')
+            .and_then(|(prefix, local)| {
+                self.context
+                    .get(prefix)
+                    .map(|namespace| format!("{namespace}{local}"))
+            })
+            .unwrap_or_else(|| compact.to_owned());
+        NamedNode::new(iri).expect("Invalid IRI")
+    }
+
+    fn id(&mut self, id: &str) -> Subject {
+        match id.strip_prefix("_:") {
+            Some(label) => self
+                .blank_nodes
+                .entry(label.to_owned())
+                .or_default()
+                .clone()
+                .into(),
+            None => self.iri(id).into(),
+        }
+    }
+
+    fn value(&mut self, value: &Json) -> Term {
+        match value {
+            Json::String(value) => Literal::new_simple_literal(value).into(),
+            Json::Number(value) => Literal::new_typed_literal(value, xsd::INTEGER).into(),
+            Json::Boolean(value) => Literal::from(*value).into(),
+            Json::Array(_) => panic!("Unexpected nested JSON array"),
+            Json::Object(object) => {
+                if let Some(Json::String(value)) = object.get("@value") {
+                    return match (object.get("@language"), object.get("@type")) {
+                        (Some(Json::String(language)), None) => {
+                            Literal::new_language_tagged_literal(value, language)
+                                .expect("Invalid language tag")
+                                .into()
+                        }
+                        (None, Some(Json::String(datatype))) => {
+                            Literal::new_typed_literal(value, self.iri(datatype)).into()
+                        }
+                        _ => panic!("Invalid JSON-LD value object"),
+                    };
+                }
+                if let Some(Json::Array(members)) = object.get("@list") {
+                    let mut list: Term = rdf::NIL.into();
+                    for member in members.iter().rev() {
+                        let member = self.value(member);
+                        let node = BlankNode::default();
+                        self.graph
+                            .insert(TripleRef::new(&node, rdf::FIRST, &member));
+                        self.graph.insert(TripleRef::new(&node, rdf::REST, &list));
+                        list = node.into();
+                    }
+                    return list;
+                }
+                self.node(object).into()
+            }
+        }
+    }
+
+    fn node(&mut self, node: &BTreeMap<String, Json>) -> Subject {
+        let subject = match node.get("@id") {
+            Some(Json::String(id)) => self.id(id),
+            _ => BlankNode::default().into(),
+        };
+        for (key, values) in node {
+            let values = match values {
+                Json::Array(values) => values.iter().collect(),
+                Json::String(_) | Json::Number(_) | Json::Boolean(_) | Json::Object(_) => {
+                    vec![values]
+                }
+            };
+            for value in values {
+                let (predicate, object) = match (key.as_str(), value) {
+                    ("@id", _) => continue,
+                    ("@type", Json::String(typ)) => (rdf::TYPE.into_owned(), self.iri(typ).into()),
+                    (_, _) => (self.iri(key), self.value(value)),
+                };
+                self.graph
+                    .insert(TripleRef::new(&subject, &predicate, &object));
+            }
+        }
+        subject
+    }
+}
+
+/**
+ * Parses the JSON-LD written by us into a graph,
+ * checking that it puts the nodes into the named graph `graph_name`.
+ * Returns the graph, the `@context` and the number of top-level nodes.
+ */
+fn parse_json_ld(data: &[u8], graph_name: &str) -> (Graph, BTreeMap<String, String>, usize) {
+    let mut reader = JsonReader::from_reader(data);
+    let Json::Object(document) = read_json_value(&mut reader, &mut Vec::new()) else {
+        panic!("The JSON-LD document is no object");
+    };
+    let Some(Json::Object(context)) = document.get("@context") else {
+        panic!("The JSON-LD document has no @context");
+    };
+    let context = context
+        .iter()
+        .map(|(prefix, namespace)| match namespace {
+            Json::String(namespace) => (prefix.clone(), namespace.clone()),
+            Json::Number(_) | Json::Boolean(_) | Json::Array(_) | Json::Object(_) => {
+                panic!("Unexpected @context entry for '{prefix}'")
+            }
+        })
+        .collect();
+    let mut json_ld = JsonLdReader {
+        context,
+        blank_nodes: HashMap::new(),
+        graph: Graph::new(),
+    };
+    let Some(Json::String(id)) = document.get("@id") else {
+        panic!("The JSON-LD document has no @id");
+    };
+    assert_eq!(json_ld.iri(id).as_str(), graph_name);
+    let Some(Json::Array(nodes)) = document.get("@graph") else {
+        panic!("The JSON-LD document has no @graph");
+    };
+    for node in nodes {
+        let Json::Object(node) = node else {
+            panic!("A @graph member is no object");
+        };
+        json_ld.node(node);
+    }
+    json_ld.graph.canonicalize();
+    (json_ld.graph, json_ld.context, nodes.len())
+}
+
+fn to_sorted_ntriples(graph: &Graph) -> String {
+    let mut lines = graph
+        .iter()
+        .map(|triple| format!("{triple} ."))
+        .collect::<Vec<_>>();
+    lines.sort();
+    lines.join("\n")
+}
+
+#[test]
+fn golden() {
+    let mut case_dirs = fs::read_dir(golden_dir())
+        .expect("Failed to list golden test cases")
+        .map(|entry| entry.expect("Failed to read golden test case").path())
+        .filter(|path| path.join("expected.ttl").is_file())
+        .collect::<Vec<_>>();
+    case_dirs.sort();
+    assert!(!case_dirs.is_empty(), "No golden test cases found");
+
+    let mut failed = Vec::new();
+    for case_dir in &case_dirs {
+        let output = run(case_dir);
+        let case = case_dir.file_name().unwrap().to_string_lossy();
+        if !output.status.success() {
+            failed.push(format!(
+                "Golden case '{case}' failed to convert:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+            continue;
+        }
+        let actual = parse_turtle(&output.stdout);
+        let expected = parse_turtle(&fs::read(case_dir.join("expected.ttl")).unwrap());
+        if actual != expected {
+            failed.push(format!(
+                "Golden case '{case}' differs;\n--- expected:\n{}\n--- actual:\n{}",
+                to_sorted_ntriples(&expected),
+                to_sorted_ntriples(&actual)
+            ));
+        }
+    }
+    assert!(failed.is_empty(), "{}", failed.join("\n\n"));
+}
+
+#[test]
+fn cardinality_conflict_as_error() {
+    let case_dir = golden_dir().join("cardinality-conflict");
+    let output = Command::new(env!("CARGO_BIN_EXE_owl2shacl"))
+        .arg("--cardinality-conflict=error")
+        .arg(case_dir.join("input.ttl"))
+        .env("RUST_LOG", "error")
+        .output()
+        .expect("Failed to run owl2shacl");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("conflicting cardinalities"));
+}
+
+#[test]
+fn minted_shape_name_collision_as_error() {
+    let case_dir = golden_dir().join("shape-name-minted-collision");
+    let output = Command::new(env!("CARGO_BIN_EXE_owl2shacl"))
+        .arg("--shape-name-collision=error")
+        .arg(case_dir.join("input.ttl"))
+        .env("RUST_LOG", "error")
+        .output()
+        .expect("Failed to run owl2shacl");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("pInverseShape> was minted for multiple shapes"));
+}
+
+#[test]
+fn turtle_output_is_stable() {
+    let case_dir = golden_dir().join("restriction");
+    let first = run(&case_dir);
+    let second = run(&case_dir);
+    assert!(first.status.success());
+    assert_eq!(first.stdout, second.stdout);
+    let turtle = String::from_utf8_lossy(&first.stdout);
+    assert!(turtle.starts_with("@prefix ex:  <https://example.org/ont#> .\n"));
+    assert!(turtle.contains("\nex:PersonShape a sh:NodeShape ;\n"));
+
+    // Multiple and shared blank nodes, which are labelled in order of appearance;
+    // with blank node shapes, the property shapes are shared between the node shapes.
+    let input = input_file(&golden_dir().join("blank-node-ranges"));
+    for args in [&[][..], &["--shape-naming", "blank-node"]] {
+        let run_case = || {
+            Command::new(env!("CARGO_BIN_EXE_owl2shacl"))
+                .args(args)
+                .arg(&input)
+                .env("RUST_LOG", "error")
+                .output()
+                .expect("Failed to run owl2shacl")
+        };
+        let first = run_case();
+        assert!(first.status.success());
+        for _ in 0..5 {
+            assert_eq!(
+                String::from_utf8_lossy(&first.stdout),
+                String::from_utf8_lossy(&run_case().stdout),
+                "Output differs between runs with {args:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn output_formats() {
+    const SHAPES_GRAPH: &str = "https://example.org/ont#shapes";
+    let case_dir = golden_dir().join("one-of");
+    let run_as = |format: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_owl2shacl"))
+            .args(["--output-format", format, "--shapes-graph", SHAPES_GRAPH])
+            .arg(case_dir.join("input.ttl"))
+            .env("RUST_LOG", "error")
+            .output()
+            .expect("Failed to run owl2shacl");
+        assert!(output.status.success());
+        output.stdout
+    };
+    let graph_name = NamedNodeRef::new(SHAPES_GRAPH).unwrap();
+    let expected = parse_turtle(&fs::read(case_dir.join("expected.ttl")).unwrap());
+    for (format, graph_format) in [
+        ("n-triples", GraphFormat::NTriples),
+        ("rdf-xml", GraphFormat::RdfXml),
+    ] {
+        let actual = parse_graph(graph_format, &run_as(format));
+        assert_eq!(actual, expected, "{format}");
+    }
+    for (format, dataset_format) in [
+        ("n-quads", DatasetFormat::NQuads),
+        ("trig", DatasetFormat::TriG),
+    ] {
+        let actual = parse_named_graph(dataset_format, &run_as(format), graph_name);
+        assert_eq!(actual, expected, "{format}");
+    }
+    let json_ld = run_as("json-ld");
+    let (actual, context, node_count) = parse_json_ld(&json_ld, SHAPES_GRAPH);
+    assert_eq!(actual, expected, "json-ld");
+    assert_eq!(
+        context.into_iter().collect::<Vec<_>>(),
+        [
+            ("ex".to_owned(), "https://example.org/ont#".to_owned()),
+            ("sh".to_owned(), "http://www.w3.org/ns/shacl#".to_owned()),
+        ]
+    );
+    // The named shapes; the property shape of ex:ShirtShape is nested
+    assert_eq!(node_count, 6);
+    let json_ld = String::from_utf8(json_ld).unwrap();
+    assert!(json_ld.contains("\"sh:in\": { \"@list\": [ \"S\", \"M\", \"L\" ] }"));
+}
+
+#[test]
+fn output_format_from_extension() {
+    let case_dir = golden_dir().join("one-of");
+    let out_file = Path::new(env!("CARGO_TARGET_TMPDIR")).join("one-of-shapes.nt");
+    let output = Command::new(env!("CARGO_BIN_EXE_owl2shacl"))
+        .arg("--output")
+        .arg(&out_file)
+        .arg(case_dir.join("input.ttl"))
+        .env("RUST_LOG", "error")
+        .output()
+        .expect("Failed to run owl2shacl");
+    assert!(output.status.success());
+    let actual = parse_graph(GraphFormat::NTriples, &fs::read(&out_file).unwrap());
+    let expected = parse_turtle(&fs::read(case_dir.join("expected.ttl")).unwrap());
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn input_format_sniffed() {
+    let case_dir = golden_dir().join("restriction");
+    let in_file = Path::new(env!("CARGO_TARGET_TMPDIR")).join("restriction-ontology");
+    fs::copy(case_dir.join("input.ttl"), &in_file).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_owl2shacl"))
+        .arg(&in_file)
+        .env("RUST_LOG", "error")
+        .output()
+        .expect("Failed to run owl2shacl");
+    assert!(output.status.success());
+    let expected = parse_turtle(&fs::read(case_dir.join("expected.ttl")).unwrap());
+    assert_eq!(parse_turtle(&output.stdout), expected);
+}
+
+#[test]
+fn parse_error_location() {
+    let in_file = Path::new(env!("CARGO_TARGET_TMPDIR")).join("broken.ttl");
+    fs::write(
+        &in_file,
+        "@prefix ex: <https://example.org/ont#> .\n\nex:a ex:b \"unterminated .\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_owl2shacl"))
+        .arg(&in_file)
+        .env("RUST_LOG", "error")
+        .output()
+        .expect("Failed to run owl2shacl");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("broken.ttl"), "{stderr}");
+    assert!(stderr.contains("at line 3"), "{stderr}");
+}
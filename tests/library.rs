@@ -0,0 +1,120 @@
+// SPDX-FileCopyrightText: 2024 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Tests of the library API:
+//! converting an ontology loaded into a store by the caller,
+//! without going through the CLI.
+
+use owl2shacl::config::OdityHandling;
+use owl2shacl::vocab::sh;
+use owl2shacl::{Config, ConversionError, Converter};
+use oxigraph::io::GraphFormat;
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{GraphNameRef, NamedNodeRef, QuadRef};
+use oxigraph::store::Store;
+
+const ONTOLOGY: &str = r"
+@prefix ex:   <https://example.org/ont#> .
+@prefix owl:  <http://www.w3.org/2002/07/owl#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+ex:Person a owl:Class .
+
+ex:knows a owl:ObjectProperty ;
+    rdfs:domain ex:Person ;
+    rdfs:range ex:Person .
+";
+
+fn ex(local: &str) -> String {
+    format!("https://example.org/ont#{local}")
+}
+
+fn load(turtle: &str) -> Store {
+    let store = Store::new().unwrap();
+    store
+        .load_graph(
+            turtle.as_bytes(),
+            GraphFormat::Turtle,
+            GraphNameRef::DefaultGraph,
+            None,
+        )
+        .unwrap();
+    store
+}
+
+fn contains(store: &Store, subj: &str, pred: NamedNodeRef, obj: NamedNodeRef) -> bool {
+    store
+        .contains(QuadRef::new(
+            NamedNodeRef::new(subj).unwrap(),
+            pred,
+            obj,
+            GraphNameRef::DefaultGraph,
+        ))
+        .unwrap()
+}
+
+#[test]
+fn convert_store() {
+    let store_owl = load(ONTOLOGY);
+    let store_shacl = Converter::new(Config::default())
+        .convert(&store_owl)
+        .unwrap();
+
+    let person_shape = ex("PersonShape");
+    let knows_shape = ex("knowsShape");
+    let person = ex("Person");
+    let person = NamedNodeRef::new(&person).unwrap();
+    let knows = ex("knows");
+    let knows = NamedNodeRef::new(&knows).unwrap();
+    assert!(contains(
+        &store_shacl,
+        &person_shape,
+        rdf::TYPE,
+        sh::NODE_SHAPE
+    ));
+    assert!(contains(
+        &store_shacl,
+        &person_shape,
+        sh::TARGET_CLASS,
+        person
+    ));
+    assert!(contains(
+        &store_shacl,
+        &person_shape,
+        sh::PROPERTY,
+        NamedNodeRef::new(&knows_shape).unwrap()
+    ));
+    assert!(contains(
+        &store_shacl,
+        &knows_shape,
+        rdf::TYPE,
+        sh::PROPERTY_SHAPE
+    ));
+    assert!(contains(&store_shacl, &knows_shape, sh::PATH, knows));
+    assert!(contains(&store_shacl, &knows_shape, sh::CLASS, person));
+    // The source store is left untouched
+    assert_eq!(store_owl.len().unwrap(), load(ONTOLOGY).len().unwrap());
+}
+
+#[test]
+fn convert_error() {
+    let store_owl = load(
+        r"
+@prefix ex:   <https://example.org/ont#> .
+@prefix owl:  <http://www.w3.org/2002/07/owl#> .
+
+ex:Thing a owl:Class ;
+    owl:equivalentClass [ owl:hasSelf true ] .
+",
+    );
+    let config = Config {
+        unsupported_construct: OdityHandling::Error,
+        ..Config::default()
+    };
+    let err = Converter::new(config).convert(&store_owl).err();
+    assert!(
+        matches!(err, Some(ConversionError::UnsupportedConstruct { .. })),
+        "{err:?}"
+    );
+}